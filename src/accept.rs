@@ -0,0 +1,242 @@
+use crate::config::Config;
+use crate::event::{EventKind, EventReader};
+use crate::fim::{self, FileStat};
+use crate::history;
+use crate::policy::Policy;
+use crate::signing::{self, Keys};
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rusqlite::{params, Connection, TransactionBehavior};
+use std::{collections::BTreeMap, path::Path};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+/// What to approve and who approves it.
+#[derive(Debug, Clone, Default)]
+pub struct AcceptRequest {
+    /// Exact paths; entries containing glob metacharacters are matched as globs.
+    pub targets: Vec<String>,
+    /// Diff JSONL written by `scan --jsonl`.
+    pub diff: Option<String>,
+    pub approver: String,
+    pub ticket: Option<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct AcceptOutcome {
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Paths left untouched, with the reason.
+    pub skipped: Vec<(String, String)>,
+    /// Generation recording the result, if anything was accepted
+    pub generation: Option<i64>,
+}
+
+/// State the approver saw in the diff; the file must still match it.
+#[derive(Debug, Default, Clone)]
+struct Expected {
+    hash: Option<String>,
+    mode: Option<String>,
+    owner: Option<String>,
+    /// Reviewed as missing: the file must still be gone.
+    gone: bool,
+}
+
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(r#"
+    CREATE TABLE IF NOT EXISTS approvals (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      ts INTEGER NOT NULL,
+      approver TEXT NOT NULL,
+      path TEXT NOT NULL,
+      action TEXT NOT NULL,
+      old_hash TEXT,
+      new_hash TEXT,
+      ticket TEXT,
+      comment TEXT
+    );
+    CREATE INDEX IF NOT EXISTS approvals_path ON approvals(path);
+    "#)?;
+    Ok(())
+}
+
+/// Best guess at the human behind the command.
+pub fn default_approver() -> String {
+    ["SUDO_USER", "USER", "USERNAME"].iter()
+        .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Folds the selected drift into the working baseline, one row at a time,
+/// and records every decision in `approvals`.
+pub fn accept(cfg: &Config, req: &AcceptRequest) -> Result<AcceptOutcome> {
+    let mut conn = fim::open_db(cfg)?;
+    let keys = Keys::from_config(cfg)?;
+    signing::check_baseline(&conn, cfg, &keys)?;
+
+    let selected = select_paths(&conn, cfg, req)?;
+    let policy = Policy::from_config(cfg)?;
+    let (exclude, _) = fim::build_excluder(&cfg.exclude)?;
+    let roots: Vec<String> = cfg.watch_paths.iter().map(|r| fim::normalize_path(Path::new(r))).collect();
+    let mut outcome = AcceptOutcome::default();
+    let ts = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    for (path, expected) in selected {
+        let old = fim::load_row(&tx, &path)?;
+        let p = Path::new(&path);
+        if p.is_file() {
+            if expected.gone {
+                outcome.skipped.push((path, "file reappeared since diff".to_string()));
+                continue;
+            }
+            // a row no scan refreshes would show up as drift forever
+            if let Some(reason) = untracked(&path, &roots, &exclude, &policy) {
+                warn!("not accepting {}: {}", path, reason);
+                outcome.skipped.push((path, reason.to_string()));
+                continue;
+            }
+            let rule = policy.for_path(&path);
+            let (hash, stat) = fim::fingerprint(p, rule, cfg)?;
+            if let Some(reason) = mismatch(&expected, &hash, &stat) {
+                warn!("not accepting {}: {}", path, reason);
+                outcome.skipped.push((path, reason));
+                continue;
+            }
+            if old.as_ref().is_some_and(|o| o.hash == hash && o.stat == stat) {
+                outcome.skipped.push((path, "already matches baseline".to_string()));
+                continue;
+            }
+            fim::upsert_row(&tx, &path, &hash, &stat)?;
+            if rule.log_mode.is_some() {
+                fim::set_prefix(&tx, &path, Some(&fim::log_prefix(p, rule, stat.size)?))?;
+            }
+            record(&tx, ts, req, &path, "update", old.map(|o| o.hash), Some(hash))?;
+            outcome.updated.push(path);
+        } else if let Some(o) = old {
+            if expected.hash.is_some() {
+                outcome.skipped.push((path, "file vanished since diff".to_string()));
+                continue;
+            }
+            tx.execute("DELETE FROM files WHERE path=?1", params![path])?;
+            record(&tx, ts, req, &path, "remove", Some(o.hash), None)?;
+            outcome.removed.push(path);
+        } else {
+            outcome.skipped.push((path, "neither on disk nor in baseline".to_string()));
+        }
+    }
+    let changed = !outcome.updated.is_empty() || !outcome.removed.is_empty();
+    if changed {
+        let comment = match &req.ticket {
+            Some(t) => format!("accepted by {} ({})", req.approver, t),
+            None => format!("accepted by {}", req.approver),
+        };
+        outcome.generation = Some(history::commit_generation(&tx, cfg, &comment, keys.signing.as_ref())?);
+    }
+    tx.commit()?;
+    info!("Accepted {} updates, {} removals by {}", outcome.updated.len(), outcome.removed.len(), req.approver);
+
+    if changed {
+        signing::resign(&conn, cfg, &keys)?;
+    }
+    Ok(outcome)
+}
+
+/// Why scans do not cover `path`, if they do not.
+fn untracked(path: &str, roots: &[String], exclude: &GlobSet, policy: &Policy) -> Option<&'static str> {
+    if !roots.iter().any(|r| Path::new(path).starts_with(r)) {
+        return Some("outside watch_paths");
+    }
+    if fim::is_excluded(Path::new(path), exclude) {
+        return Some("excluded");
+    }
+    if policy.for_path(path).ignored() {
+        return Some("ignored by rule");
+    }
+    None
+}
+
+fn mismatch(exp: &Expected, hash: &str, stat: &FileStat) -> Option<String> {
+    if exp.hash.as_deref().is_some_and(|h| h != hash) {
+        return Some("content changed since diff".to_string());
+    }
+    if exp.mode.as_deref().is_some_and(|m| m != stat.mode_str()) {
+        return Some("mode changed since diff".to_string());
+    }
+    if exp.owner.as_deref().is_some_and(|o| o != stat.owner_str()) {
+        return Some("owner changed since diff".to_string());
+    }
+    None
+}
+
+fn record(conn: &Connection, ts: i64, req: &AcceptRequest, path: &str, action: &str,
+          old_hash: Option<String>, new_hash: Option<String>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO approvals(ts, approver, path, action, old_hash, new_hash, ticket, comment)
+         VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![ts, req.approver, path, action, old_hash, new_hash, req.ticket, req.comment],
+    )?;
+    Ok(())
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+/// Resolves targets and diff entries to normalized paths.
+fn select_paths(conn: &Connection, cfg: &Config, req: &AcceptRequest) -> Result<BTreeMap<String, Expected>> {
+    let mut out = BTreeMap::new();
+
+    let mut globs = GlobSetBuilder::new();
+    let mut any_glob = false;
+    for t in &req.targets {
+        if is_glob(t) {
+            globs.add(Glob::new(t).with_context(|| format!("invalid glob {}", t))?);
+            any_glob = true;
+        } else {
+            out.insert(fim::normalize_path(Path::new(t)), Expected::default());
+        }
+    }
+
+    if any_glob {
+        let set = globs.build()?;
+        let (exclude, _) = fim::build_excluder(&cfg.exclude)?;
+        // tracked rows cover deletions, a walk of the roots covers new files
+        let mut stmt = conn.prepare("SELECT path FROM files")?;
+        for path in stmt.query_map([], |r| r.get::<_, String>(0))? {
+            let path = path?;
+            if set.is_match(&path) {
+                out.entry(path).or_default();
+            }
+        }
+        for root in &cfg.watch_paths {
+            for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+                let p = entry.path();
+                if !p.is_file() || fim::is_excluded(p, &exclude) { continue; }
+                let norm = fim::normalize_path(p);
+                if set.is_match(&norm) {
+                    out.entry(norm).or_default();
+                }
+            }
+        }
+    }
+
+    if let Some(diff) = &req.diff {
+        for evt in EventReader::open(diff)? {
+            let evt = evt.with_context(|| format!("read diff {}", diff))?;
+            // entries without a new hash were reviewed against the baseline content
+            let pinned = match evt.new_hash {
+                Some(h) => Some(h),
+                None if evt.kind == EventKind::Missing => None,
+                None => fim::load_row(conn, &evt.path)?.map(|o| o.hash),
+            };
+            let e = out.entry(evt.path).or_default();
+            e.gone |= evt.kind == EventKind::Missing;
+            e.hash = pinned.or(e.hash.take());
+            e.mode = evt.new_mode.or(e.mode.take());
+            e.owner = evt.new_owner.or(e.owner.take());
+        }
+    }
+    Ok(out)
+}
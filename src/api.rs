@@ -0,0 +1,253 @@
+use crate::config::Config;
+use crate::event::{self, AuditEvent, EventKind};
+use crate::eventdb;
+use crate::fim;
+use crate::metrics::Metrics;
+use crate::query;
+use crate::sink::EventSink;
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use futures_util::stream::{self, Stream};
+use globset::{Glob, GlobMatcher};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{path::Path, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Default window of `GET /events` without `since`.
+const DEFAULT_SINCE: &str = "1h";
+
+/// Events a `/events/stream` client may fall behind before it skips ahead.
+const STREAM_BUFFER: usize = 1024;
+
+#[derive(Clone)]
+struct ApiState {
+    cfg: Arc<Config>,
+    jsonl: Arc<String>,
+    metrics: Metrics,
+    live: EventStream,
+}
+
+/// Read-only JSON endpoints over the running `watch`:
+/// `GET /files?path=`, `GET /events?since=&limit=`, `GET /status`
+/// and the SSE feed `GET /events/stream?path=&kind=`.
+pub fn router(cfg: Config, jsonl: String, metrics: Metrics, live: EventStream) -> Router {
+    let state = ApiState { cfg: Arc::new(cfg), jsonl: Arc::new(jsonl), metrics, live };
+    Router::new()
+        .route("/files", get(files))
+        .route("/events", get(events))
+        .route("/events/stream", get(events_stream))
+        .route("/status", get(status))
+        .with_state(state)
+}
+
+/// Sink behind `/events/stream`. Emitting never waits for clients: each one
+/// has a bounded buffer, and a client that overflows it misses the oldest
+/// events and gets a `lagged` message with their count instead.
+#[derive(Clone)]
+pub struct EventStream {
+    tx: broadcast::Sender<Arc<AuditEvent>>,
+}
+
+impl EventStream {
+    pub fn new() -> Self {
+        Self { tx: broadcast::channel(STREAM_BUFFER).0 }
+    }
+
+    /// Connected `/events/stream` clients.
+    pub fn clients(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventSink for EventStream {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        // no clients is not an error
+        let _ = self.tx.send(Arc::new(evt.clone()));
+        Ok(())
+    }
+}
+
+/// A baseline row as served by `/files`.
+#[derive(Debug, Serialize)]
+struct FileInfo {
+    path: String,
+    hash: String,
+    hash_alg: String,
+    size: u64,
+    mtime: u64,
+    mode: String,
+    uid: u32,
+    gid: u32,
+    inode: u64,
+    device: u64,
+    nlink: u64,
+    ctime: i64,
+    /// Bytes covered by the `log_mode` prefix hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix_len: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FilesParams {
+    path: String,
+}
+
+async fn files(State(s): State<ApiState>, Query(q): Query<FilesParams>) -> Result<Json<FileInfo>, ApiError> {
+    let path = fim::normalize_path(Path::new(&q.path));
+    let cfg = s.cfg.clone();
+    let key = path.clone();
+    let row = blocking(move || fim::load_row(&open_ro(&cfg)?, &key)).await?;
+    let row = row.ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("{path} is not in the baseline")))?;
+    Ok(Json(FileInfo {
+        path,
+        hash: row.hash,
+        hash_alg: s.cfg.hash_alg.clone(),
+        size: row.stat.size,
+        mtime: row.stat.mtime,
+        mode: row.stat.mode_str(),
+        uid: row.stat.uid,
+        gid: row.stat.gid,
+        inode: row.stat.inode,
+        device: row.stat.device,
+        nlink: row.stat.nlink,
+        ctime: row.stat.ctime,
+        prefix_len: row.prefix.map(|(len, _)| len),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsParams {
+    /// Anything `query --since` takes
+    since: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+/// Recent events from the `events` table, or from the JSONL log and its
+/// rotations when `events_table` is off.
+async fn events(State(s): State<ApiState>, Query(q): Query<EventsParams>) -> Result<Json<Vec<AuditEvent>>, ApiError> {
+    let since = query::parse_time(q.since.as_deref().unwrap_or(DEFAULT_SINCE))
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let events = blocking(move || {
+        if s.cfg.events_table {
+            return eventdb::recent(&open_ro(&s.cfg)?, since, q.limit);
+        }
+        query::recent(&s.jsonl, since, q.limit)
+    }).await?;
+    Ok(Json(events))
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamParams {
+    /// Glob over `path` (and `old_path` of renames)
+    path: Option<String>,
+    /// Comma-separated kinds, e.g. `modify,delete`
+    kind: Option<String>,
+}
+
+struct StreamFilter {
+    path: Option<GlobMatcher>,
+    kinds: Vec<EventKind>,
+}
+
+impl StreamFilter {
+    fn matches(&self, e: &AuditEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&e.kind))
+            && self.path.as_ref().is_none_or(|g| g.is_match(&e.path) || e.old_path.as_ref().is_some_and(|p| g.is_match(p)))
+    }
+}
+
+/// Server-Sent Events: one `audit` message per event as it is written.
+async fn events_stream(
+    State(s): State<ApiState>,
+    Query(q): Query<StreamParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let bad = |e: anyhow::Error| ApiError(StatusCode::BAD_REQUEST, format!("{e:#}"));
+    let path = match &q.path {
+        Some(g) => Some(Glob::new(g).with_context(|| format!("invalid glob {g}")).map_err(bad)?.compile_matcher()),
+        None => None,
+    };
+    let kinds = q.kind.as_deref().unwrap_or_default()
+        .split(',')
+        .filter(|k| !k.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<EventKind>>>()
+        .map_err(bad)?;
+    let filter = Arc::new(StreamFilter { path, kinds });
+
+    let rx = s.live.tx.subscribe();
+    let body = stream::unfold(rx, move |mut rx| {
+        let filter = filter.clone();
+        async move {
+            loop {
+                let msg = match rx.recv().await {
+                    Ok(e) if filter.matches(&e) => Event::default().event("audit").json_data(&*e),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(n)) => Ok(Event::default().event("lagged").data(n.to_string())),
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((msg, rx));
+            }
+        }
+    });
+    Ok(Sse::new(body).keep_alive(KeepAlive::default()))
+}
+
+async fn status(State(s): State<ApiState>) -> Json<serde_json::Value> {
+    let m = &s.metrics;
+    Json(json!({
+        "host": event::host(),
+        "roots": s.cfg.watch_paths,
+        "tracked_files": m.tracked_files.get(),
+        "events_table": s.cfg.events_table,
+        "stream_clients": s.live.clients(),
+        "watcher": {
+            "up": m.watcher_up.get() == 1,
+            "errors": m.watcher_errors.get(),
+        },
+    }))
+}
+
+/// The API never writes: open without creating or migrating anything.
+fn open_ro(cfg: &Config) -> Result<Connection> {
+    Connection::open_with_flags(&cfg.baseline_db, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .with_context(|| format!("open {}", cfg.baseline_db))
+}
+
+/// SQLite and log reads stay off the async workers.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(f).await.map_err(anyhow::Error::from)?.map_err(ApiError::from)
+}
+
+struct ApiError(StatusCode, String);
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+fn default_limit() -> usize { 1000 }
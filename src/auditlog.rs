@@ -0,0 +1,216 @@
+use crate::event::{AuditEvent, EventKind};
+use anyhow::{Context, Result};
+use std::{fs, io::{BufRead, BufReader, Read, Seek, SeekFrom, Write}};
+use tracing::warn;
+
+/// `prev` value of the first chained record in a log.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// `source` of the `log_truncated` record written into the audit log itself
+/// when it shrinks under us.
+pub const TRUNCATION_SOURCE: &str = "audit_log";
+
+/// Append-only JSONL writer that keeps the BLAKE3 hash of the last line,
+/// so every new record can point at its predecessor.
+pub struct AuditLog {
+    path: String,
+    file: fs::File,
+    head: String,
+    /// Size after our last write; a smaller file means someone truncated it.
+    len: u64,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending and recovers the chain head from its last line.
+    pub fn open_append(path: &str) -> Result<Self> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open audit log {}", path))?;
+        let head = match last_line(&mut file)? {
+            Some(line) => link_hash(&line),
+            None => GENESIS.to_string(),
+        };
+        let len = file.metadata()?.len();
+        Ok(Self { path: path.to_string(), file, head, len })
+    }
+
+    /// Creates (or truncates) `path` and starts a fresh chain.
+    pub fn create(path: &str) -> Result<Self> {
+        fs::File::create(path).with_context(|| format!("create audit log {}", path))?;
+        Self::open_append(path)
+    }
+
+    /// Opens the path again, e.g. after logrotate moved the file away.
+    pub fn reopen(&mut self) -> Result<()> {
+        *self = Self::open_append(&self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Bytes written so far.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hash the next record has to carry in its `prev` field.
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    /// Notices an external truncation (logrotate `copytruncate`, or someone
+    /// dropping records). The chain goes on from what is left, after a
+    /// `log_truncated` record that keeps the lost head and size, which
+    /// `verify-log` reports. Returns the current size.
+    pub fn refresh(&mut self) -> Result<u64> {
+        let cur = self.file.metadata()?.len();
+        if cur >= self.len {
+            return Ok(cur);
+        }
+        warn!("audit log {} shrank from {} to {} bytes; chain head {} is gone", self.path, self.len, cur, self.head);
+        let prev = match last_line(&mut self.file)? {
+            Some(line) => link_hash(&line),
+            None => GENESIS.to_string(),
+        };
+        let marker = AuditEvent {
+            prev,
+            source: Some(TRUNCATION_SOURCE.to_string()),
+            old_hash: Some(self.head.clone()),
+            old_size: Some(self.len),
+            size: Some(cur),
+            ..AuditEvent::new(EventKind::LogTruncated, self.path.clone())
+        };
+        let line = serde_json::to_string(&marker)?;
+        self.file.write_all(format!("{line}\n").as_bytes())?;
+        self.head = link_hash(line.as_bytes());
+        self.len = cur + line.len() as u64 + 1;
+        Ok(self.len)
+    }
+
+    /// Writes one serialized record (without trailing newline) and advances the head.
+    pub fn append_line(&mut self, line: &str) -> Result<()> {
+        let cur = self.refresh()?;
+        let mut buf = String::with_capacity(line.len() + 1);
+        buf.push_str(line);
+        buf.push('\n');
+        self.file.write_all(buf.as_bytes())?;
+        self.head = link_hash(line.as_bytes());
+        self.len = cur + buf.len() as u64;
+        Ok(())
+    }
+}
+
+/// First place where the chain does not hold.
+#[derive(Debug, Clone)]
+pub struct BrokenLink {
+    /// 1-based line number of the record whose `prev` is wrong.
+    pub line: usize,
+    pub expected: String,
+    pub found: Option<String>,
+}
+
+/// A `log_truncated` record the writer left after the log shrank.
+#[derive(Debug, Clone)]
+pub struct Truncation {
+    /// 1-based line number of the marker.
+    pub line: usize,
+    /// Chain head before the cut: the last record that was lost.
+    pub lost_head: String,
+    /// Log size before the cut, in bytes.
+    pub lost_size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChainReport {
+    pub records: usize,
+    /// Leading records written before chaining existed (no `prev` field).
+    pub legacy: usize,
+    pub broken: Option<BrokenLink>,
+    /// The chain holds, but records were cut off at these points.
+    pub truncated: Vec<Truncation>,
+}
+
+/// Walks the log and reports the first record whose `prev` does not match
+/// the hash of the line before it.
+pub fn verify_log(path: &str) -> Result<ChainReport> {
+    let f = fs::File::open(path).with_context(|| format!("open audit log {}", path))?;
+    let reader = BufReader::new(f);
+    let mut expected = GENESIS.to_string();
+    let mut records = 0usize;
+    let mut legacy = 0usize;
+    let mut chained = false;
+    let mut truncated = Vec::new();
+
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        if line.is_empty() { continue; }
+        records += 1;
+        let value = serde_json::from_slice::<serde_json::Value>(&line).ok();
+        let prev = value.as_ref()
+            .and_then(|v| v.get("prev").and_then(|p| p.as_str()).map(str::to_string));
+        if let Some(v) = value.as_ref().filter(|v| v["kind"] == "log_truncated" && v["source"] == TRUNCATION_SOURCE) {
+            truncated.push(Truncation {
+                line: i + 1,
+                lost_head: v["old_hash"].as_str().unwrap_or_default().to_string(),
+                lost_size: v["old_size"].as_u64().unwrap_or_default(),
+            });
+        }
+        match prev {
+            None if !chained => legacy += 1,
+            Some(p) if p == expected => chained = true,
+            found => {
+                return Ok(ChainReport {
+                    records,
+                    legacy,
+                    broken: Some(BrokenLink { line: i + 1, expected, found }),
+                    truncated,
+                });
+            }
+        }
+        expected = link_hash(&line);
+    }
+    Ok(ChainReport { records, legacy, broken: None, truncated })
+}
+
+fn link_hash(line: &[u8]) -> String {
+    blake3::hash(line).to_hex().to_string()
+}
+
+/// Reads the last non-empty line without loading the whole file.
+fn last_line(f: &mut fs::File) -> Result<Option<Vec<u8>>> {
+    const CHUNK: u64 = 64 * 1024;
+    let len = f.seek(SeekFrom::End(0))?;
+    let mut end = len;
+    let mut tail: Vec<u8> = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let mut buf = vec![0u8; (end - start) as usize];
+        f.seek(SeekFrom::Start(start))?;
+        f.read_exact(&mut buf)?;
+        buf.extend_from_slice(&tail);
+        tail = buf;
+        end = start;
+
+        let trimmed = trim_newlines(&tail);
+        if let Some(pos) = trimmed.iter().rposition(|b| *b == b'\n') {
+            return Ok(Some(trimmed[pos + 1..].to_vec()));
+        }
+    }
+    let trimmed = trim_newlines(&tail);
+    if trimmed.is_empty() { Ok(None) } else { Ok(Some(trimmed.to_vec())) }
+}
+
+fn trim_newlines(b: &[u8]) -> &[u8] {
+    let mut n = b.len();
+    while n > 0 && b[n - 1] == b'\n' { n -= 1; }
+    &b[..n]
+}
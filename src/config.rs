@@ -1,9 +1,16 @@
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use anyhow::{Context, Result};
 use std::fs;
+use crate::export::ExportConfig;
+use crate::http::HttpConfig;
+use crate::policy::Rule;
+use crate::rotate::RotationConfig;
+use crate::signing::SignaturePolicy;
+use crate::syslog::SyslogConfig;
+use crate::webhook::WebhookConfig;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub baseline_db: String,
     pub metrics_bind: String,
@@ -14,6 +21,48 @@ pub struct Config {
     pub hash_alg: String,
     #[serde(default = "default_debounce_ms")]
     pub debounce_ms: u64,
+    /// Ed25519 secret key used to sign the baseline manifest
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// Ed25519 public key for verification (derived from signing_key if unset)
+    #[serde(default)]
+    pub verify_key: Option<String>,
+    /// "enforce" refuses an unverified baseline, "warn" only logs
+    #[serde(default)]
+    pub signature_policy: SignaturePolicy,
+    /// Hashing workers for init/scan; 0 = one per CPU
+    #[serde(default)]
+    pub hash_threads: usize,
+    /// Per-path `[[rule]]` sections, first match wins
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+    /// HTTP webhook for audit events
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+    /// RFC 5424 syslog output
+    #[serde(default)]
+    pub syslog: Option<SyslogConfig>,
+    /// Rotation of the `watch` JSONL log
+    #[serde(default)]
+    pub rotation: Option<RotationConfig>,
+    /// Also record `watch` events in the `events` table of baseline_db
+    #[serde(default)]
+    pub events_table: bool,
+    /// `watch` start-up check against the baseline: "foreground", "background" or "off"
+    #[serde(default = "default_reconcile")]
+    pub reconcile: String,
+    /// `watch`: full rescan every this many seconds to catch missed events; 0 = off
+    #[serde(default)]
+    pub rescan_interval: u64,
+    /// Read budget of that rescan in bytes per second; 0 = unlimited
+    #[serde(default)]
+    pub rescan_bytes_per_sec: u64,
+    /// TLS, auth and a separate admin listener for the HTTP server
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+    /// Textfile / Pushgateway export of `init` and `scan` metrics
+    #[serde(default)]
+    pub metrics_export: Option<ExportConfig>,
 }
 
 impl Config {
@@ -26,6 +75,34 @@ impl Config {
     }
 }
 
+/// Serde defaults for the optional keys; required ones as in config.sample.toml.
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            baseline_db: "baseline.db".to_string(),
+            metrics_bind: "127.0.0.1:9977".to_string(),
+            watch_paths: vec![],
+            exclude: vec![],
+            hash_alg: default_hash_alg(),
+            debounce_ms: default_debounce_ms(),
+            signing_key: None,
+            verify_key: None,
+            signature_policy: SignaturePolicy::default(),
+            hash_threads: 0,
+            rules: vec![],
+            webhook: None,
+            syslog: None,
+            rotation: None,
+            events_table: false,
+            reconcile: default_reconcile(),
+            rescan_interval: 0,
+            rescan_bytes_per_sec: 0,
+            http: None,
+            metrics_export: None,
+        }
+    }
+}
 
 fn default_hash_alg() -> String { "blake3".to_string() }
 fn default_debounce_ms() -> u64 { 250 }
+fn default_reconcile() -> String { "foreground".to_string() }
@@ -0,0 +1,192 @@
+use crate::fim::now_ms;
+use crate::policy::Severity;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io::{BufRead, BufReader, Read}, str::FromStr, sync::OnceLock};
+
+/// Bumped whenever a field changes meaning or is removed; adding fields is compatible.
+/// Records written before versioning parse with `schema_version = 0`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What happened to the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    // watch
+    Create,
+    Modify,
+    Delete,
+    Rename,
+    TimesChange,
+    /// Found by the periodic rescan: the watcher never reported it.
+    MissedEvent,
+    // scan
+    Changed,
+    Added,
+    Missing,
+    MtimeReset,
+    // both
+    PermChange,
+    OwnerChange,
+    LogTruncated,
+    LogRewritten,
+    /// Kind written by a newer version.
+    #[serde(other)]
+    Unknown,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Create => "create",
+            EventKind::Modify => "modify",
+            EventKind::Delete => "delete",
+            EventKind::Rename => "rename",
+            EventKind::TimesChange => "times_change",
+            EventKind::MissedEvent => "missed_event",
+            EventKind::Changed => "changed",
+            EventKind::Added => "added",
+            EventKind::Missing => "missing",
+            EventKind::MtimeReset => "mtime_reset",
+            EventKind::PermChange => "perm_change",
+            EventKind::OwnerChange => "owner_change",
+            EventKind::LogTruncated => "log_truncated",
+            EventKind::LogRewritten => "log_rewritten",
+            EventKind::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match serde_json::from_value(serde_json::Value::String(s.to_string()))? {
+            EventKind::Unknown => anyhow::bail!("unknown event kind: {s}"),
+            kind => Ok(kind),
+        }
+    }
+}
+
+/// One audit record, as written to the JSONL log and handed to every sink.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    #[serde(default)]
+    pub schema_version: u32,
+    /// BLAKE3 of the previous line in the same log, see `auditlog`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prev: String,
+    /// Unix time, ms
+    pub ts: i64,
+    /// Machine the event was observed on
+    #[serde(default)]
+    pub host: String,
+    pub kind: EventKind,
+    pub path: String,
+    /// What noticed the change when it was not the live watcher: `reconcile` or `rescan`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// On `missed_event`: the kind the watcher should have reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missed: Option<EventKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_mtime: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_mtime: Option<u64>,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// false for rules with `action = "log"`
+    #[serde(default = "default_alert")]
+    pub alert: bool,
+}
+
+impl AuditEvent {
+    pub fn new(kind: EventKind, path: String) -> Self {
+        AuditEvent {
+            schema_version: SCHEMA_VERSION, prev: String::new(), ts: now_ms() as i64, host: host().to_string(), kind, path, source: None, missed: None, old_path: None, old_hash: None, new_hash: None, size: None, old_size: None,
+            old_mode: None, new_mode: None, old_owner: None, new_owner: None,
+            old_mtime: None, new_mtime: None,
+            severity: Severity::default(), rule: None, alert: true,
+        }
+    }
+}
+
+/// Host name of this machine, read once.
+pub fn host() -> &'static str {
+    static HOST: OnceLock<String> = OnceLock::new();
+    HOST.get_or_init(|| {
+        fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|s| s.trim().to_string())
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .unwrap_or_default()
+    })
+}
+
+/// Parses a JSONL audit log line by line; blank lines are skipped.
+pub struct EventReader<R> {
+    lines: std::io::Lines<BufReader<R>>,
+    line: usize,
+}
+
+impl EventReader<fs::File> {
+    pub fn open(path: &str) -> Result<Self> {
+        let f = fs::File::open(path).with_context(|| format!("open audit log {}", path))?;
+        Ok(Self::new(f))
+    }
+}
+
+impl<R: Read> EventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { lines: BufReader::new(reader).lines(), line: 0 }
+    }
+}
+
+impl<R: Read> Iterator for EventReader<R> {
+    type Item = Result<AuditEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(l) => l,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let n = self.line;
+            return Some(serde_json::from_str(&line).with_context(|| format!("line {n}: invalid audit event")));
+        }
+    }
+}
+
+fn default_alert() -> bool { true }
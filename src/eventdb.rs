@@ -0,0 +1,47 @@
+use crate::event::AuditEvent;
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+/// `events` table: the audit trail next to the baseline, filled by `watch`
+/// when `events_table = true`.
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(r#"
+    CREATE TABLE IF NOT EXISTS events (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      ts INTEGER NOT NULL,
+      kind TEXT NOT NULL,
+      path TEXT NOT NULL,
+      old_path TEXT,
+      old_hash TEXT,
+      new_hash TEXT,
+      severity TEXT NOT NULL,
+      rule TEXT,
+      event TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_path ON events(path, ts);
+    CREATE INDEX IF NOT EXISTS events_ts ON events(ts);
+    "#)?;
+    Ok(())
+}
+
+pub(crate) fn insert(conn: &Connection, e: &AuditEvent) -> Result<()> {
+    conn.execute(
+        "INSERT INTO events (ts, kind, path, old_path, old_hash, new_hash, severity, rule, event)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![e.ts, e.kind.as_str(), e.path, e.old_path, e.old_hash, e.new_hash, e.severity.as_str(), e.rule,
+            serde_json::to_string(e)?],
+    )?;
+    Ok(())
+}
+
+/// Newest `limit` events with `ts >= since`, oldest first.
+pub(crate) fn recent(conn: &Connection, since: i64, limit: usize) -> Result<Vec<AuditEvent>> {
+    let mut stmt = conn.prepare("SELECT event FROM events WHERE ts >= ?1 ORDER BY ts DESC, id DESC LIMIT ?2")?;
+    let rows = stmt.query_map(params![since, limit as i64], |r| r.get::<_, String>(0))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(serde_json::from_str(&row?)?);
+    }
+    out.reverse();
+    Ok(out)
+}
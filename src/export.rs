@@ -0,0 +1,122 @@
+use crate::config::Config;
+use crate::event;
+use crate::metrics::Metrics;
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use prometheus::{proto::LabelPair, Encoder, TextEncoder};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, time::Duration};
+use tracing::{info, warn};
+
+/// Families that describe a one-shot run; watcher and API metrics stay out.
+/// The run time is in `node_textfile_mtime_seconds` / `push_time_seconds`.
+const RUN_FAMILIES: [&str; 7] = [
+    "fim_scan_drift_files",
+    "fim_run_duration_seconds",
+    "fim_run_files_hashed",
+    "fim_tracked_files",
+    "fim_hash_duration_seconds",
+    "fim_hashed_bytes_total",
+    "fim_hash_errors_total",
+];
+
+/// `[metrics_export]` section: where `init` and `scan` leave their metrics,
+/// since no HTTP server runs for them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// node_exporter textfile collector directory; `sentra_fim_<command>.prom` is written there
+    #[serde(default)]
+    pub textfile_dir: Option<String>,
+    /// Pushgateway base URL, e.g. `http://pushgateway:9091`
+    #[serde(default)]
+    pub pushgateway: Option<String>,
+    #[serde(default = "default_job")]
+    pub job: String,
+    /// Pushgateway `instance` label; the hostname if unset
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Writes and/or pushes the run metrics of `command` (`init`, `scan`) as
+/// configured. Failures are logged: a cron run should not fail on them.
+pub fn run_metrics(cfg: &Config, metrics: &Metrics, command: &str) {
+    let Some(exp) = &cfg.metrics_export else { return };
+    let body = match encode(metrics, command) {
+        Ok(b) => b,
+        Err(e) => return warn!("metrics export: {e:#}"),
+    };
+    if let Some(dir) = &exp.textfile_dir {
+        match write_textfile(Path::new(dir), command, &body) {
+            Ok(path) => info!("metrics written to {path}"),
+            Err(e) => warn!("metrics textfile: {e:#}"),
+        }
+    }
+    if let Some(url) = &exp.pushgateway {
+        if let Err(e) = push(url, exp, command, &body) {
+            warn!("metrics push to {url}: {e:#}");
+        }
+    }
+}
+
+/// Run families in the text format, each sample labelled `command`.
+pub fn encode(metrics: &Metrics, command: &str) -> Result<String> {
+    let mut families = metrics.registry().gather();
+    families.retain(|f| RUN_FAMILIES.contains(&f.get_name()));
+    for family in &mut families {
+        for m in family.mut_metric().iter_mut() {
+            let mut label = LabelPair::default();
+            label.set_name("command".to_string());
+            label.set_value(command.to_string());
+            m.mut_label().insert(0, label);
+        }
+    }
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&families, &mut buf).context("encode metrics")?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// Atomic for the collector: written next to the target, then renamed over it.
+fn write_textfile(dir: &Path, command: &str, body: &str) -> Result<String> {
+    let path = dir.join(format!("sentra_fim_{command}.prom"));
+    // the collector only reads *.prom, so the temp file is never half-read
+    let tmp = dir.join(format!(".sentra_fim_{command}.prom.tmp"));
+    fs::write(&tmp, body).with_context(|| format!("write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("rename to {}", path.display()))?;
+    Ok(path.display().to_string())
+}
+
+/// PUT replaces the whole group, so stale series from an earlier run vanish.
+fn push(url: &str, exp: &ExportConfig, command: &str, body: &str) -> Result<()> {
+    let instance = exp.instance.as_deref().unwrap_or(event::host());
+    if instance.is_empty() {
+        bail!("hostname is empty: set metrics_export.instance");
+    }
+    let target = format!("{}/metrics/{}/{}/{}", url.trim_end_matches('/'),
+        grouping("job", &exp.job)?, grouping("instance", instance)?, grouping("command", command)?);
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_millis(exp.timeout_ms))
+        .build()
+        .put(&target)
+        .set("Content-Type", "text/plain; version=0.0.4")
+        .send_string(body)?;
+    Ok(())
+}
+
+/// One `<name>/<value>` pair of the grouping key; values that are not plain
+/// URL path segments use the `<name>@base64/<value>` form.
+fn grouping(name: &str, value: &str) -> Result<String> {
+    if value.is_empty() {
+        bail!("empty pushgateway label {name}");
+    }
+    let plain = value.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.~".contains(&b)) && value != "." && value != "..";
+    Ok(if plain {
+        format!("{name}/{value}")
+    } else {
+        format!("{name}@base64/{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value))
+    })
+}
+
+fn default_job() -> String { "sentra_fim".to_string() }
+fn default_timeout_ms() -> u64 { 10_000 }
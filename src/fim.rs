@@ -1,130 +1,375 @@
 
+use crate::accept;
+use crate::auditlog::AuditLog;
 use crate::config::Config;
+use crate::history;
+use crate::policy::{Action, Check, LogMode, Policy, Rule};
+use crate::pool::{self, RateLimit};
+use crate::rotate::RotatingLog;
 use crate::metrics::Metrics;
+use crate::signing::{self, Keys};
+use crate::event::{AuditEvent, EventKind as Kind};
+use crate::eventdb;
+use crate::export;
+use crate::sink::{EventSink, FanOut};
+use crate::syslog::Syslog;
+use crate::webhook::Webhook;
 use anyhow::{Context, Result};
-use notify::{RecommendedWatcher, RecursiveMode, Watcher, EventKind, event::{ModifyKind, RenameMode}};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher, EventKind, event::ModifyKind};
 use rusqlite::{params, Connection, TransactionBehavior};
-use std::{fs, path::{Path, PathBuf}, sync::mpsc, collections::HashMap};
-use walkdir::WalkDir;
+use std::{fs, io, path::Path, sync::{mpsc, Arc, atomic::{AtomicBool, Ordering}}, collections::{BTreeMap, HashMap}, time::{Duration, Instant}};
 use globset::{Glob, GlobSetBuilder};
-use serde::Serialize;
 use tracing::{info, warn, debug};
 use time::OffsetDateTime;
 
-#[derive(Debug, Serialize)]
-struct AuditEvent<'a> {
-    ts: i128,
-    kind: &'a str,
-    path: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    old_path: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    old_hash: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    new_hash: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    size: Option<u64>,
+/// Stat fields tracked alongside the content hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FileStat {
+    pub(crate) size: u64,
+    pub(crate) mtime: u64,
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    pub(crate) mode: u32,
+    pub(crate) inode: u64,
+    pub(crate) device: u64,
+    pub(crate) nlink: u64,
+    /// Inode change time in ns; cannot be set from userspace, unlike mtime.
+    pub(crate) ctime: i64,
 }
 
+impl FileStat {
+    #[cfg(unix)]
+    pub(crate) fn from_meta(meta: &fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        FileStat {
+            size: meta.len(),
+            mtime: mtime_secs(meta),
+            uid: meta.uid(),
+            gid: meta.gid(),
+            mode: meta.mode(),
+            inode: meta.ino(),
+            device: meta.dev(),
+            nlink: meta.nlink(),
+            ctime: meta.ctime() * 1_000_000_000 + meta.ctime_nsec(),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn from_meta(meta: &fs::Metadata) -> Self {
+        // no uid/gid/inode here; keep the readonly bit as the only "mode"
+        FileStat {
+            size: meta.len(),
+            mtime: mtime_secs(meta),
+            mode: if meta.permissions().readonly() { 0o444 } else { 0o644 },
+            nlink: 1,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn perm_differs(&self, other: &FileStat) -> bool {
+        self.mode & 0o7777 != other.mode & 0o7777
+    }
+
+    pub(crate) fn owner_differs(&self, other: &FileStat) -> bool {
+        self.uid != other.uid || self.gid != other.gid
+    }
+
+    /// True when stat data alone is enough to trust the baseline hash.
+    /// ctime is part of it on unix because, unlike mtime, it cannot be forged.
+    pub(crate) fn same_identity(&self, other: &FileStat) -> bool {
+        self.size == other.size
+            && self.mtime == other.mtime
+            && self.inode == other.inode
+            && self.device == other.device
+            && (cfg!(not(unix)) || (other.ctime != 0 && self.ctime == other.ctime))
+    }
+
+    /// ctime moved but mtime did not move forward, and no chmod/chown explains it.
+    pub(crate) fn mtime_looks_reset(&self, old: &FileStat) -> bool {
+        cfg!(unix)
+            && old.ctime != 0
+            && self.ctime != old.ctime
+            && self.mtime <= old.mtime
+            && !self.perm_differs(old)
+            && !self.owner_differs(old)
+    }
+
+    pub(crate) fn times_differ(&self, other: &FileStat) -> bool {
+        self.mtime != other.mtime
+    }
+
+    pub(crate) fn mode_str(&self) -> String {
+        format!("{:o}", self.mode & 0o7777)
+    }
+
+    pub(crate) fn owner_str(&self) -> String {
+        format!("{}:{}", self.uid, self.gid)
+    }
+}
+
+/// A row of the `files` table.
+#[derive(Debug, Clone)]
+pub(crate) struct BaselineRow {
+    pub(crate) hash: String,
+    pub(crate) stat: FileStat,
+    /// (length, BLAKE3) of the leading bytes, kept for `log_mode` rules.
+    pub(crate) prefix: Option<(u64, String)>,
+}
+
+/// How long the watcher queue must stay quiet before the manifest is re-signed.
+const RESIGN_IDLE: Duration = Duration::from_secs(2);
+
+/// Columns written by `upsert_row`.
+const STAT_COLUMNS: &str = "hash, size, mtime, uid, gid, mode, inode, device, nlink, ctime";
+
+/// Every per-file column; used for snapshots and the signed digest.
+pub(crate) const FILE_COLUMNS: &str = "hash, size, mtime, uid, gid, mode, inode, device, nlink, ctime, prefix_len, prefix_hash";
+
 pub fn build_baseline(cfg: &Config) -> Result<()> {
-    let conn = Connection::open(&cfg.baseline_db)?;
-    init_schema(&conn)?;
+    build_generation(cfg, None).map(|_| ())
+}
+
+/// Indexes the watch roots into a new baseline generation and activates it.
+/// Earlier generations stay in `baseline_files` for `generations diff/rollback`.
+pub fn build_generation(cfg: &Config, comment: Option<&str>) -> Result<i64> {
+    let start = Instant::now();
+    let metrics = Metrics::try_new()?;
+    let mut conn = open_db(cfg)?;
     let (globset, _) = build_excluder(&cfg.exclude)?;
+    let policy = Policy::from_config(cfg)?;
+    let keys = Keys::from_config(cfg)?;
 
     let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    // `files` is the working copy of the active generation
     tx.execute("DELETE FROM files", [])?;
     let mut count = 0usize;
-    for root in &cfg.watch_paths {
-        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-            let p = entry.path();
-            if !p.is_file() { continue; }
-            if is_excluded(p, &globset) { continue; }
-            if let Ok(meta) = p.metadata() {
-                let (hash, size, mtime) = hash_meta(p, cfg)?;
-                let norm = normalize_path(p);
-                tx.execute(
-                    "INSERT OR REPLACE INTO files(path, hash, size, mtime) VALUES(?1, ?2, ?3, ?4)",
-                    params![norm, hash, size as i64, mtime as i64]
-                )?;
+    pool::walk_parallel(cfg, &globset, |p| hash_file(p, cfg, &policy, &metrics), |_, res| {
+        match res? {
+            Some((norm, hash, stat, prefix)) => {
                 count += 1;
+                upsert_row(&tx, &norm, &hash, &stat)?;
+                set_prefix(&tx, &norm, prefix.as_ref())
             }
+            None => Ok(()),
         }
-    }
+    })?;
+    let generation = history::insert_generation(&tx, comment, &history::config_hash(cfg))?;
+    history::snapshot(&tx, generation, keys.signing.as_ref())?;
+    history::activate(&tx, generation)?;
     tx.commit()?;
-    info!("Baseline: {} files indexed (transactional)", count);
-    Ok(())
+    info!("Baseline: {} files indexed as generation {} (transactional)", count, generation);
+    metrics.tracked_files.set(count as i64);
+
+    signing::resign(&conn, cfg, &keys)?;
+    if keys.signing.is_some() {
+        info!("Baseline manifest signed: {}", signing::manifest_path(cfg));
+    }
+    metrics.record_run(start.elapsed(), count);
+    export::run_metrics(cfg, &metrics, "init");
+    Ok(generation)
 }
 
-pub async fn watch_loop(cfg: Config, jsonl_path: String, metrics: Metrics) -> Result<()> {
-    let conn = Connection::open(&cfg.baseline_db)?;
-    init_schema(&conn)?;
+/// `live` gets every event after the configured outputs, e.g. `api::EventStream`.
+pub async fn watch_loop(cfg: Config, jsonl_path: String, metrics: Metrics, live: impl EventSink + 'static) -> Result<()> {
+    let log = AuditLog::open_append(&jsonl_path)?;
+    let mut out = match &cfg.rotation {
+        Some(r) => config_sinks(&cfg, Some(RotatingLog::new(log, r.clone())?))?,
+        None => config_sinks(&cfg, Some(log))?,
+    };
+    out.push(live);
+    watch_with(cfg, metrics, &mut out).await
+}
+
+/// Live monitoring loop; every event goes to `out`.
+pub async fn watch_with(cfg: Config, metrics: Metrics, out: &mut dyn EventSink) -> Result<()> {
+    if !matches!(cfg.reconcile.as_str(), "foreground" | "background" | "off") {
+        anyhow::bail!("unknown reconcile mode {:?} (expected foreground, background or off)", cfg.reconcile);
+    }
+    let conn = open_db(&cfg)?;
+    let keys = Keys::from_config(&cfg)?;
+    signing::check_baseline(&conn, &cfg, &keys)?;
 
     // count tracked_files
     let tracked: i64 = conn.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))?;
-    metrics.tracked_files.set(tracked as i64);
+    metrics.tracked_files.set(tracked);
 
     let (globset, _raw) = build_excluder(&cfg.exclude)?;
+    let policy = Policy::from_config(&cfg)?;
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+    let depth = metrics.queue_depth.clone();
+    let mut watcher = RecommendedWatcher::new(move |res| {
+        depth.inc();
+        let _ = tx.send(res);
+    }, notify::Config::default())
         .context("create watcher")?;
 
     for p in &cfg.watch_paths {
         watcher.watch(Path::new(p), RecursiveMode::Recursive)?;
     }
     info!("Watching {} roots", cfg.watch_paths.len());
+    metrics.watcher_up.set(1);
+    let roots: Vec<String> = cfg.watch_paths.iter().map(|p| normalize_path(Path::new(p))).collect();
+    let out = &mut Counted { out, metrics: &metrics, roots };
 
-    let mut jsonl = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&jsonl_path)
-        .context("open jsonl")?;
+    // the manifest is re-signed once the event queue goes idle
+    let mut dirty = false;
+    // changes made while we were down; live events queue up meanwhile
+    let mut reconciling = None;
+    match cfg.reconcile.as_str() {
+        "foreground" => {
+            // waited for here, but the heartbeat keeps going: /healthz must not
+            // fail (and a liveness probe restart us) during a long reconcile
+            let done = spawn_reconcile(&cfg, &metrics);
+            let drift = loop {
+                metrics.heartbeat.set((now_ms() / 1000) as i64);
+                match done.recv_timeout(RESIGN_IDLE) {
+                    Ok(res) => break res?,
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("reconcile thread died"),
+                }
+            };
+            dirty = apply_drift(&conn, &cfg, out, &metrics, &policy, drift, Origin::Reconcile);
+            metrics.ready.set(1);
+        }
+        "background" => reconciling = Some(spawn_reconcile(&cfg, &metrics)),
+        _ => metrics.ready.set(1),
+    }
+    let mut rescans = spawn_rescans(&cfg, &metrics);
+    let mut rescanned = None;
 
     // simple debounce map
-    let mut last_evt: HashMap<String, i128> = HashMap::new();
+    let mut last_evt: HashMap<(String, EventClass), i128> = HashMap::new();
     let window = cfg.debounce_ms as i128;
+    let hup = reopen_on_sighup()?;
 
     loop {
-        let event = rx.recv().expect("watcher channel closed");
+        metrics.heartbeat.set((now_ms() / 1000) as i64);
+        let finished = reconciling.as_ref().and_then(|rx| match rx.try_recv() {
+            Ok(res) => Some(res),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!("reconcile thread died"))),
+        });
+        if let Some(res) = finished {
+            reconciling = None;
+            match res {
+                Ok(drift) => {
+                    dirty |= apply_drift(&conn, &cfg, out, &metrics, &policy, drift, Origin::Reconcile);
+                    metrics.ready.set(1);
+                }
+                Err(e) => {
+                    metrics.handler_error("reconcile");
+                    warn!("background reconcile failed: {e:#}");
+                }
+            }
+        }
+        if rescanned.is_none() {
+            match rescans.as_ref().map(|rx| rx.try_recv()) {
+                Some(Ok(res)) => rescanned = Some(res),
+                Some(Err(mpsc::TryRecvError::Disconnected)) => {
+                    rescans = None;
+                    rescanned = Some(Err(anyhow::anyhow!("rescan thread died")));
+                }
+                _ => {}
+            }
+        }
+        // queued live events go first: the rescan may have seen their changes,
+        // and the handlers' re-check then leaves only what the watcher missed
+        if reconciling.is_none() && metrics.queue_depth.get() == 0 {
+            match rescanned.take() {
+                Some(Ok(drift)) => dirty |= apply_drift(&conn, &cfg, out, &metrics, &policy, drift, Origin::Rescan),
+                Some(Err(e)) => {
+                    metrics.handler_error("rescan");
+                    warn!("rescan failed: {e:#}");
+                }
+                None => {}
+            }
+        }
+        if hup.swap(false, Ordering::Relaxed) {
+            match out.reopen() {
+                Ok(()) => info!("SIGHUP: audit outputs reopened"),
+                Err(e) => {
+                    metrics.handler_error("reopen");
+                    warn!("reopen after SIGHUP failed: {e}");
+                }
+            }
+        }
+        let event = match rx.recv_timeout(RESIGN_IDLE) {
+            Ok(Ok(ev)) => {
+                metrics.queue_depth.dec();
+                ev
+            }
+            Ok(Err(e)) => {
+                metrics.queue_depth.dec();
+                metrics.watcher_errors.inc();
+                metrics.watcher_last_error.set((now_ms() / 1000) as i64);
+                warn!("watcher error: {e}");
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if dirty {
+                    if let Err(e) = signing::resign(&conn, &cfg, &keys) {
+                        metrics.handler_error("resign");
+                        warn!("baseline re-sign error: {e}");
+                    }
+                    dirty = false;
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                metrics.watcher_up.set(0);
+                metrics.ready.set(0);
+                anyhow::bail!("watcher channel closed")
+            }
+        };
         // Debug log kind
         debug!("event: kind={:?} paths={:?}", event.kind, event.paths);
 
         match &event.kind {
-            EventKind::Modify(ModifyKind::Name(_mode)) => {
-                // Try to handle rename with two paths (from, to)
-                if event.paths.len() >= 2 {
-                    let from = &event.paths[0];
-                    let to = &event.paths[1];
-                    if is_excluded(from, &globset) || is_excluded(to, &globset) {
-                        continue;
-                    }
-                    if debounce_hit(&mut last_evt, from, window) && debounce_hit(&mut last_evt, to, window) {
-                        continue;
-                    }
-                    if let Err(e) = handle_rename(&conn, from, to, &mut jsonl, &metrics, &cfg) {
-                        warn!("rename handle error: {e}");
-                    }
-                } else {
-                    // Fallback: treat as delete/create if we don't have both paths
-                    for p in &event.paths {
-                        if is_excluded(p, &globset) { continue; }
-                        if debounce_hit(&mut last_evt, p, window) { continue; }
+            // rename with two paths (from, to)
+            EventKind::Modify(ModifyKind::Name(_mode)) if event.paths.len() >= 2 => {
+                let from = &event.paths[0];
+                let to = &event.paths[1];
+                if is_excluded(from, &globset) || is_excluded(to, &globset) {
+                    continue;
+                }
+                if debounce_hit(&mut last_evt, EventClass::Name, from, window) && debounce_hit(&mut last_evt, EventClass::Name, to, window) {
+                    continue;
+                }
+                if let Err(e) = in_tx(&conn, &cfg, out, |db, o| handle_rename(db, from, to, o, &metrics, &cfg, &policy)) {
+                    metrics.handler_error("rename");
+                    warn!("rename handle error: {e}");
+                }
+                dirty = true;
+            }
+            // single-path From/To halves are followed by the paired event above;
+            // debouncing them would swallow it
+            EventKind::Modify(ModifyKind::Name(_)) => {}
+            EventKind::Modify(ModifyKind::Metadata(_)) => {
+                for p in &event.paths {
+                    if is_excluded(p, &globset) { continue; }
+                    if debounce_hit(&mut last_evt, EventClass::Meta, p, window) { continue; }
+                    if let Err(e) = in_tx(&conn, &cfg, out, |db, o| handle_metadata(db, p, o, &metrics, &cfg, &policy)) {
+                        metrics.handler_error("metadata");
+                        warn!("metadata handle error: {e}");
                     }
+                    dirty = true;
                 }
             }
             EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
-                for p in event.paths {
-                    if is_excluded(&p, &globset) { continue; }
-                    if debounce_hit(&mut last_evt, &p, window) { continue; }
+                for p in &event.paths {
+                    if is_excluded(p, &globset) { continue; }
+                    if debounce_hit(&mut last_evt, EventClass::Data, p, window) { continue; }
                     if let EventKind::Remove(_) = &event.kind {
-                        if let Err(e) = handle_delete(&conn, &p, &mut jsonl, &metrics) {
+                        if let Err(e) = in_tx(&conn, &cfg, out, |db, o| handle_delete(db, p, o, &metrics, &policy)) {
+                            metrics.handler_error("delete");
                             warn!("delete handle error: {e}");
                         }
-                    } else {
-                        if let Err(e) = handle_upsert(&conn, &p, &mut jsonl, &metrics, &cfg) {
-                            warn!("upsert handle error: {e}");
-                        }
+                    } else if let Err(e) = in_tx(&conn, &cfg, out, |db, o| handle_upsert(db, p, o, &metrics, &cfg, &policy)) {
+                        metrics.handler_error("upsert");
+                        warn!("upsert handle error: {e}");
                     }
+                    dirty = true;
                 }
             }
             _ => {}
@@ -132,83 +377,462 @@ pub async fn watch_loop(cfg: Config, jsonl_path: String, metrics: Metrics) -> Re
     }
 }
 
+/// Runs one handler in a transaction. Its events go into the `events` table
+/// (if enabled) before the commit and to `out` only after it.
+fn in_tx(
+    conn: &Connection,
+    cfg: &Config,
+    out: &mut dyn EventSink,
+    handler: impl FnOnce(&Connection, &mut dyn EventSink) -> Result<()>,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let mut events: Vec<AuditEvent> = Vec::new();
+    let changes = |db: &Connection| db.query_row("SELECT total_changes()", [], |r| r.get::<_, i64>(0));
+    let before = changes(&tx)?;
+    handler(&tx, &mut events)?;
+    if changes(&tx)? != before {
+        history::mark_dirty(&tx)?;
+    }
+    if cfg.events_table {
+        for e in &events {
+            eventdb::insert(&tx, e)?;
+        }
+    }
+    tx.commit()?;
+    let mut first = None;
+    for e in &events {
+        if let Err(err) = out.emit(e) {
+            first.get_or_insert(err);
+        }
+    }
+    first.map_or(Ok(()), Err)
+}
+
+/// How a path drifted from the baseline while nobody was watching;
+/// ordered so that the strongest finding per path wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Drift {
+    /// perm, owner or times only
+    Meta,
+    Content,
+    Gone,
+}
+
+/// Runs the scan logic (read-only, in parallel) and returns the drifted paths.
+fn find_drift(cfg: &Config, metrics: &Metrics, limit: Option<&RateLimit>) -> Result<BTreeMap<String, Drift>> {
+    let mut found: Vec<AuditEvent> = Vec::new();
+    let sum = scan_metered(cfg, ScanMode::Full, &mut found, metrics, limit)?;
+    metrics.record_scan(&sum);
+    let mut drift = BTreeMap::new();
+    for e in found {
+        let d = match e.kind {
+            Kind::Missing => Drift::Gone,
+            Kind::PermChange | Kind::OwnerChange => Drift::Meta,
+            Kind::Changed if e.old_hash == e.new_hash => Drift::Meta,
+            _ => Drift::Content,
+        };
+        let slot = drift.entry(e.path).or_insert(d);
+        *slot = (*slot).max(d);
+    }
+    Ok(drift)
+}
+
+/// Start-up reconcile scan on a thread of its own.
+fn spawn_reconcile(cfg: &Config, metrics: &Metrics) -> mpsc::Receiver<Result<BTreeMap<String, Drift>>> {
+    let (done_tx, done_rx) = mpsc::channel();
+    let (cfg, metrics) = (drift_config(cfg), metrics.clone());
+    std::thread::spawn(move || {
+        let _ = done_tx.send(find_drift(&cfg, &metrics, None));
+    });
+    done_rx
+}
+
+/// Full rescan every `rescan_interval` seconds on a thread of its own, reads
+/// paced by `rescan_bytes_per_sec`. At most one result waits for the loop.
+fn spawn_rescans(cfg: &Config, metrics: &Metrics) -> Option<mpsc::Receiver<Result<BTreeMap<String, Drift>>>> {
+    if cfg.rescan_interval == 0 {
+        return None;
+    }
+    let cfg = drift_config(cfg);
+    let every = Duration::from_secs(cfg.rescan_interval);
+    let metrics = metrics.clone();
+    let (tx, rx) = mpsc::sync_channel(1);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(every);
+        let limit = (cfg.rescan_bytes_per_sec > 0).then(|| RateLimit::new(cfg.rescan_bytes_per_sec));
+        if tx.send(find_drift(&cfg, &metrics, limit.as_ref())).is_err() {
+            return;
+        }
+    });
+    Some(rx)
+}
+
+/// `cfg` for scans beside the live loop: the baseline was verified when
+/// `watch` started, and the manifest lags its rows until the next re-sign.
+fn drift_config(cfg: &Config) -> Config {
+    Config { signing_key: None, verify_key: None, ..cfg.clone() }
+}
+
+/// Why drift is applied; decides how its events are marked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// start-up check: changed while `watch` was down
+    Reconcile,
+    /// periodic rescan: changed under the running watcher, which missed it
+    Rescan,
+}
+
+impl Origin {
+    fn as_str(self) -> &'static str {
+        match self {
+            Origin::Reconcile => "reconcile",
+            Origin::Rescan => "rescan",
+        }
+    }
+}
+
+/// Brings the baseline up to date for each drifted path through the live
+/// handlers, which re-check the file, so a change already handled live is not
+/// reported twice. Events are marked with `origin` as their `source`. True if
+/// any path was handled.
+fn apply_drift(
+    conn: &Connection,
+    cfg: &Config,
+    out: &mut dyn EventSink,
+    metrics: &Metrics,
+    policy: &Policy,
+    drift: BTreeMap<String, Drift>,
+    origin: Origin,
+) -> bool {
+    if !drift.is_empty() {
+        info!("{}: {} paths drifted from the baseline", origin.as_str(), drift.len());
+    }
+    for (path, d) in &drift {
+        metrics.heartbeat.set((now_ms() / 1000) as i64);
+        let p = Path::new(path);
+        let res = in_tx(conn, cfg, out, |db, o| {
+            let o = &mut Sourced { out: o, origin };
+            match d {
+                Drift::Gone if !p.exists() => handle_delete(db, p, o, metrics, policy),
+                Drift::Meta => handle_metadata(db, p, o, metrics, cfg, policy),
+                _ => handle_upsert(db, p, o, metrics, cfg, policy),
+            }
+        });
+        if let Err(e) = res {
+            metrics.handler_error(origin.as_str());
+            warn!("{} {path}: {e}", origin.as_str());
+        }
+    }
+    !drift.is_empty()
+}
+
+/// Marks events that did not come from the live watcher; what a rescan
+/// finds becomes a `missed_event` carrying the kind the watcher missed.
+struct Sourced<'a> {
+    out: &'a mut dyn EventSink,
+    origin: Origin,
+}
+
+impl EventSink for Sourced<'_> {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        let mut evt = evt.clone();
+        evt.source = Some(self.origin.as_str().to_string());
+        if self.origin == Origin::Rescan {
+            evt.missed = Some(evt.kind);
+            evt.kind = Kind::MissedEvent;
+        }
+        self.out.emit(&evt)
+    }
+}
+
+/// Counts events per watch root and kind on their way to `out`.
+struct Counted<'a> {
+    out: &'a mut dyn EventSink,
+    metrics: &'a Metrics,
+    roots: Vec<String>,
+}
+
+impl EventSink for Counted<'_> {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        self.metrics.count_event(&self.roots, &evt.path, evt.kind.as_str());
+        self.out.emit(evt)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        self.out.reopen()
+    }
+}
+
+/// Flag raised on every SIGHUP (logrotate's move-and-signal).
+fn reopen_on_sighup() -> Result<Arc<AtomicBool>> {
+    let flag = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sig = signal(SignalKind::hangup()).context("install SIGHUP handler")?;
+        let raised = flag.clone();
+        tokio::spawn(async move {
+            while sig.recv().await.is_some() {
+                raised.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+    Ok(flag)
+}
+
+/// How much `scan` trusts stat data when deciding whether to hash a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanMode {
+    /// Skip hashing when size, mtime, ctime and inode match the baseline.
+    Fast,
+    /// Hash every file.
+    #[default]
+    Full,
+    /// Hash every file, and hash twice where ctime moved but mtime did not.
+    Paranoid,
+}
+
+impl std::str::FromStr for ScanMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fast" => Ok(ScanMode::Fast),
+            "full" => Ok(ScanMode::Full),
+            "paranoid" => Ok(ScanMode::Paranoid),
+            other => anyhow::bail!("unknown scan mode {other:?} (expected fast, full or paranoid)"),
+        }
+    }
+}
+
+/// Counts reported at the end of a scan.
+#[derive(Debug, Clone, Default)]
+pub struct ScanSummary {
+    pub added: usize,
+    pub changed: usize,
+    pub perm: usize,
+    pub owner: usize,
+    pub missing: usize,
+    pub mtime_reset: usize,
+    pub log_truncated: usize,
+    pub log_rewritten: usize,
+    /// Files accepted from stat data alone (fast mode).
+    pub trusted: usize,
+    pub hashed: usize,
+    /// Files hashed a second time because their mtime looked reset.
+    pub rechecked: usize,
+    /// Files that vanished or could not be read during the walk.
+    pub skipped: usize,
+}
+
+/// Result of probing one file in a scan worker.
+struct Probe<'p> {
+    rule: &'p Rule,
+    norm: String,
+    hash: String,
+    stat: FileStat,
+    old: Option<BaselineRow>,
+    route: Route,
+    log: Option<LogVerdict>,
+}
+
+enum Route {
+    Trusted,
+    Hashed,
+    /// ctime moved while mtime stayed put or went back: possible timestomping.
+    Rechecked,
+}
+
 pub fn scan_diff(cfg: &Config, jsonl_out: Option<String>) -> Result<()> {
-    let conn = Connection::open(&cfg.baseline_db)?;
-    init_schema(&conn)?;
+    scan_with(cfg, jsonl_out, ScanMode::Full).map(|_| ())
+}
 
+pub fn scan_with(cfg: &Config, jsonl_out: Option<String>, mode: ScanMode) -> Result<ScanSummary> {
+    let mut out = match jsonl_out {
+        Some(p) => config_sinks(cfg, Some(AuditLog::create(&p).context("open diff jsonl")?))?,
+        None => config_sinks(cfg, None::<AuditLog>)?.with(ScanPrinter),
+    };
+    let start = Instant::now();
+    let metrics = Metrics::try_new()?;
+    let sum = scan_metered(cfg, mode, &mut out, &metrics, None)?;
+    metrics.record_scan(&sum);
+    metrics.record_run(start.elapsed(), sum.hashed);
+    export::run_metrics(cfg, &metrics, "scan");
+    println!("Summary -> added: {}, changed: {}, perm: {}, owner: {}, missing: {}, mtime_reset: {}, log_truncated: {}, log_rewritten: {}",
+        sum.added, sum.changed, sum.perm, sum.owner, sum.missing, sum.mtime_reset, sum.log_truncated, sum.log_rewritten);
+    println!("Paths -> trusted: {}, hashed: {}, rechecked: {}, skipped: {}", sum.trusted, sum.hashed, sum.rechecked, sum.skipped);
+    Ok(sum)
+}
+
+/// Compares the filesystem with the baseline and sends drift to `out`.
+pub fn scan_into(cfg: &Config, mode: ScanMode, out: &mut dyn EventSink) -> Result<ScanSummary> {
+    scan_metered(cfg, mode, out, &Metrics::try_new()?, None)
+}
+
+/// `scan_into` that also records hashing and the scan time in `metrics`;
+/// `limit` caps the read rate of all workers together.
+pub fn scan_metered(
+    cfg: &Config,
+    mode: ScanMode,
+    out: &mut dyn EventSink,
+    metrics: &Metrics,
+    limit: Option<&RateLimit>,
+) -> Result<ScanSummary> {
+    let conn = open_db(cfg)?;
+    signing::check_baseline(&conn, cfg, &Keys::from_config(cfg)?)?;
+
+    let tracked: i64 = conn.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))?;
+    metrics.tracked_files.set(tracked);
     let (globset, _) = build_excluder(&cfg.exclude)?;
-    let mut added = 0usize;
-    let mut changed = 0usize;
-    let mut missing = 0usize;
+    let policy = Policy::from_config(cfg)?;
+    let mut sum = ScanSummary::default();
 
     let mut known = std::collections::HashSet::new();
-    let mut out = if let Some(p) = jsonl_out {
-        Some(fs::OpenOptions::new().create(true).truncate(true).write(true).open(p)
-            .context("open diff jsonl")?)
-    } else { None };
 
     // check current FS for create/modify
-    for root in &cfg.watch_paths {
-        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-            let p = entry.path();
-            if !p.is_file() { continue; }
-            if is_excluded(p, &globset) { continue; }
-            let meta = match p.metadata() { Ok(m) => m, Err(_) => continue };
-            let (hash, size, mtime) = hash_meta(p, cfg)?;
-            let norm = normalize_path(p);
+    pool::walk_parallel_with(
+        cfg,
+        &globset,
+        || Connection::open_with_flags(&cfg.baseline_db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY),
+        |db, p| match db {
+            Ok(db) => probe(db, p, cfg, &policy, mode, metrics, limit),
+            Err(e) => Err(anyhow::anyhow!("open baseline for worker: {e}")),
+        },
+        |p, res| {
+            let res = match res {
+                Err(e) if matches!(io_kind(&e), Some(io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied)) => {
+                    // one file going away mid-walk must not fail the whole scan;
+                    // a vanished one is then reported missing, an unreadable one is not
+                    warn!("skipping {}: {e:#}", p.display());
+                    sum.skipped += 1;
+                    if io_kind(&e) == Some(io::ErrorKind::PermissionDenied) {
+                        known.insert(normalize_path(&p));
+                    }
+                    return Ok(());
+                }
+                res => res?,
+            };
+            let Some(Probe { rule, norm, hash, stat, old, route, log }) = res else { return Ok(()) };
             known.insert(norm.clone());
+            match route {
+                Route::Trusted => sum.trusted += 1,
+                Route::Hashed => sum.hashed += 1,
+                Route::Rechecked => { sum.hashed += 1; sum.rechecked += 1; }
+            }
 
-            let mut stmt = conn.prepare("SELECT hash, size, mtime FROM files WHERE path=?1")?;
-            let row = stmt.query_row(params![norm.clone()], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, i64>(2)?)));
-            match row {
-                Ok((old_hash, old_size, old_mtime)) => {
-                    if old_hash != hash || old_size as u64 != size || old_mtime as u64 != mtime {
-                        changed += 1;
-                        if let Some(f) = &mut out {
-                            write_jsonl(f, AuditEvent {
-                                ts: now_ms(), kind: "changed", path: norm.clone(),
-                                old_path: None, old_hash: Some(old_hash), new_hash: Some(hash), size: Some(size)
-                            })?;
-                        } else {
-                            println!("CHANGED: {}", norm);
-                        }
+            match old {
+                Some(old) => {
+                    if let Some(kind) = log.and_then(log_kind) {
+                        if kind == Kind::LogTruncated { sum.log_truncated += 1 } else { sum.log_rewritten += 1 }
+                        write_event(out, rule, AuditEvent {
+                            size: Some(stat.size), old_size: Some(old.stat.size),
+                            ..AuditEvent::new(kind, norm.clone())
+                        })?;
                     }
-                }
-                Err(_) => {
-                    added += 1;
-                    if let Some(f) = &mut out {
-                        write_jsonl(f, AuditEvent {
-                            ts: now_ms(), kind: "added", path: norm.clone(),
-                            old_path: None, old_hash: None, new_hash: Some(hash), size: Some(size)
+                    let content = rule.hashes_content() && (old.hash != hash || old.stat.size != stat.size);
+                    if content || (rule.tracks_times() && old.stat.mtime != stat.mtime) {
+                        sum.changed += 1;
+                        write_event(out, rule, AuditEvent {
+                            old_hash: Some(old.hash.clone()), new_hash: Some(hash.clone()), size: Some(stat.size),
+                            ..AuditEvent::new(Kind::Changed, norm.clone())
+                        })?;
+                    }
+                    if matches!(route, Route::Rechecked) && old.hash != hash {
+                        sum.mtime_reset += 1;
+                        write_event(out, rule, AuditEvent {
+                            old_hash: Some(old.hash.clone()), new_hash: Some(hash.clone()),
+                            old_mtime: Some(old.stat.mtime), new_mtime: Some(stat.mtime),
+                            ..AuditEvent::new(Kind::MtimeReset, norm.clone())
                         })?;
-                    } else {
-                        println!("ADDED: {}", norm);
+                    }
+                    if rule.checks(Check::Perm) && old.stat.perm_differs(&stat) {
+                        sum.perm += 1;
+                        write_event(out, rule, perm_event(&norm, &old.stat, &stat))?;
+                    }
+                    if rule.checks(Check::Owner) && old.stat.owner_differs(&stat) {
+                        sum.owner += 1;
+                        write_event(out, rule, owner_event(&norm, &old.stat, &stat))?;
                     }
                 }
+                None => {
+                    sum.added += 1;
+                    write_event(out, rule, AuditEvent {
+                        new_hash: Some(hash), size: Some(stat.size),
+                        ..AuditEvent::new(Kind::Added, norm.clone())
+                    })?;
+                }
             }
-        }
-    }
+            Ok(())
+        },
+    )?;
 
     // find removed
     let mut stmt = conn.prepare("SELECT path FROM files")?;
     let paths = stmt.query_map([], |r| r.get::<_, String>(0))?;
     for r in paths {
         let path: String = r?;
-        if !known.contains(&path) {
-            missing += 1;
-            if let Some(f) = &mut out {
-                write_jsonl(f, AuditEvent {
-                    ts: now_ms(), kind: "missing", path: path.clone(),
-                    old_path: None, old_hash: None, new_hash: None, size: None
-                })?;
-            } else {
-                println!("MISSING: {}", path);
+        let rule = policy.for_path(&path);
+        if !known.contains(&path) && !rule.ignored() {
+            sum.missing += 1;
+            write_event(out, rule, AuditEvent::new(Kind::Missing, path.clone()))?;
+        }
+    }
+    metrics.last_scan.set((now_ms() / 1000) as i64);
+    Ok(sum)
+}
+
+/// Decides per file whether stat data is enough or the content must be hashed.
+fn probe<'p>(
+    db: &Connection,
+    p: &Path,
+    cfg: &Config,
+    policy: &'p Policy,
+    mode: ScanMode,
+    metrics: &Metrics,
+    limit: Option<&RateLimit>,
+) -> Result<Option<Probe<'p>>> {
+    let norm = normalize_path(p);
+    let rule = policy.for_path(&norm);
+    if rule.ignored() { return Ok(None); }
+    let old = load_row(db, &norm)?;
+
+    if mode == ScanMode::Fast || !rule.hashes_content() {
+        if let Some(o) = &old {
+            let stat = FileStat::from_meta(&fs::metadata(p).inspect_err(|_| metrics.hash_errors.inc())?);
+            if stat.same_identity(&o.stat) {
+                return Ok(Some(Probe { rule, norm, hash: o.hash.clone(), stat, old, route: Route::Trusted, log: None }));
+            }
+            if !rule.hashes_content() {
+                let log = match rule.log_mode {
+                    Some(_) => Some(check_log(p, rule, o, &stat).inspect_err(|_| metrics.hash_errors.inc())?.0),
+                    None => None,
+                };
+                return Ok(Some(Probe { rule, norm, hash: o.hash.clone(), stat, old, route: Route::Trusted, log }));
             }
         }
     }
 
-    println!("Summary -> added: {added}, changed: {changed}, missing: {missing}");
-    Ok(())
+    let (hash, stat) = fingerprint_metered(p, rule, cfg, metrics, limit)?;
+    let reset = mode == ScanMode::Paranoid && old.as_ref().is_some_and(|o| stat.mtime_looks_reset(&o.stat));
+    if reset {
+        // a second, independent read: catches content swapped while hashing
+        let (again, stat2) = fingerprint_metered(p, rule, cfg, metrics, limit)?;
+        if again != hash {
+            warn!("{} changed between two reads", norm);
+        }
+        return Ok(Some(Probe { rule, norm, hash: again, stat: stat2, old, route: Route::Rechecked, log: None }));
+    }
+    Ok(Some(Probe { rule, norm, hash, stat, old, route: Route::Hashed, log: None }))
+}
+
+/// Opens the baseline database and brings its schema up to date.
+pub(crate) fn open_db(cfg: &Config) -> Result<Connection> {
+    let conn = Connection::open(&cfg.baseline_db)?;
+    init_schema(&conn)?;
+    history::init_schema(&conn)?;
+    accept::init_schema(&conn)?;
+    eventdb::init_schema(&conn)?;
+    Ok(conn)
 }
 
 fn init_schema(conn: &Connection) -> Result<()> {
@@ -218,13 +842,151 @@ fn init_schema(conn: &Connection) -> Result<()> {
       path TEXT PRIMARY KEY,
       hash TEXT NOT NULL,
       size INTEGER NOT NULL,
-      mtime INTEGER NOT NULL
+      mtime INTEGER NOT NULL,
+      uid INTEGER NOT NULL DEFAULT 0,
+      gid INTEGER NOT NULL DEFAULT 0,
+      mode INTEGER NOT NULL DEFAULT 0,
+      inode INTEGER NOT NULL DEFAULT 0,
+      device INTEGER NOT NULL DEFAULT 0,
+      nlink INTEGER NOT NULL DEFAULT 0,
+      ctime INTEGER NOT NULL DEFAULT 0,
+      prefix_len INTEGER,
+      prefix_hash TEXT
     );
     "#)?;
+    // baselines created before the stat columns existed
+    for col in ["uid", "gid", "mode", "inode", "device", "nlink", "ctime"] {
+        if !has_column(conn, "files", col)? {
+            conn.execute_batch(&format!("ALTER TABLE files ADD COLUMN {col} INTEGER NOT NULL DEFAULT 0"))?;
+        }
+    }
+    for (col, ty) in [("prefix_len", "INTEGER"), ("prefix_hash", "TEXT")] {
+        if !has_column(conn, "files", col)? {
+            conn.execute_batch(&format!("ALTER TABLE files ADD COLUMN {col} {ty}"))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn has_column(conn: &Connection, table: &str, col: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |r| r.get::<_, String>(1))?;
+    for n in names {
+        if n? == col { return Ok(true); }
+    }
+    Ok(false)
+}
+
+pub(crate) fn load_row(conn: &Connection, path: &str) -> Result<Option<BaselineRow>> {
+    let sql = format!("SELECT {FILE_COLUMNS} FROM files WHERE path=?1");
+    let row = conn.query_row(&sql, params![path], |r| {
+        Ok(BaselineRow {
+            hash: r.get(0)?,
+            stat: FileStat {
+                size: r.get::<_, i64>(1)? as u64,
+                mtime: r.get::<_, i64>(2)? as u64,
+                uid: r.get::<_, i64>(3)? as u32,
+                gid: r.get::<_, i64>(4)? as u32,
+                mode: r.get::<_, i64>(5)? as u32,
+                inode: r.get::<_, i64>(6)? as u64,
+                device: r.get::<_, i64>(7)? as u64,
+                nlink: r.get::<_, i64>(8)? as u64,
+                ctime: r.get(9)?,
+            },
+            prefix: match (r.get::<_, Option<i64>>(10)?, r.get::<_, Option<String>>(11)?) {
+                (Some(len), Some(h)) => Some((len as u64, h)),
+                _ => None,
+            },
+        })
+    });
+    match row {
+        Ok(r) => Ok(Some(r)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Inserts or updates the stat columns of a row; the log prefix is kept.
+pub(crate) fn upsert_row(conn: &Connection, path: &str, hash: &str, st: &FileStat) -> Result<()> {
+    conn.execute(
+        &format!("INSERT INTO files(path, {STAT_COLUMNS}) VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+          ON CONFLICT(path) DO UPDATE SET hash=excluded.hash, size=excluded.size, mtime=excluded.mtime,
+            uid=excluded.uid, gid=excluded.gid, mode=excluded.mode, inode=excluded.inode,
+            device=excluded.device, nlink=excluded.nlink, ctime=excluded.ctime"),
+        params![path, hash, st.size as i64, st.mtime as i64, st.uid as i64, st.gid as i64,
+            st.mode as i64, st.inode as i64, st.device as i64, st.nlink as i64, st.ctime],
+    )?;
+    Ok(())
+}
+
+/// Updates mode, owner and link count only; hash and times are kept.
+fn set_access(conn: &Connection, path: &str, st: &FileStat) -> Result<()> {
+    conn.execute(
+        "UPDATE files SET uid=?1, gid=?2, mode=?3, nlink=?4 WHERE path=?5",
+        params![st.uid as i64, st.gid as i64, st.mode as i64, st.nlink as i64, path],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn set_prefix(conn: &Connection, path: &str, prefix: Option<&(u64, String)>) -> Result<()> {
+    conn.execute(
+        "UPDATE files SET prefix_len=?1, prefix_hash=?2 WHERE path=?3",
+        params![prefix.map(|p| p.0 as i64), prefix.map(|p| p.1.as_str()), path],
+    )?;
     Ok(())
 }
 
-fn build_excluder(patterns: &[String]) -> Result<(globset::GlobSet, Vec<Glob>)> {
+/// BLAKE3 over the part of the first `len` bytes of `p` that `append_only`
+/// guards: the leading `window` bytes and the `window` bytes up to `len`.
+/// Bytes in between are not read, so an edit there goes unnoticed. For
+/// `len <= window` this is the plain hash of the first `len` bytes.
+pub(crate) fn prefix_hash(p: &Path, len: u64, window: u64) -> Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut f = fs::File::open(p)?;
+    let mut hasher = blake3::Hasher::new();
+    let head = len.min(window);
+    std::io::copy(&mut (&mut f).take(head), &mut hasher)?;
+    let tail = len.saturating_sub(window).max(head);
+    f.seek(SeekFrom::Start(tail))?;
+    std::io::copy(&mut f.take(len - tail), &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// What to store for a `log_mode` file of `size` bytes.
+pub(crate) fn log_prefix(p: &Path, rule: &Rule, size: u64) -> Result<(u64, String)> {
+    Ok((size, prefix_hash(p, size, rule.prefix_bytes)?))
+}
+
+/// Outcome of checking a `log_mode` file against its stored size and prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogVerdict {
+    Appended,
+    Truncated,
+    Rewritten,
+}
+
+/// Compares a log file with its baseline row and returns the verdict plus the
+/// prefix to store for the next comparison. `append_only` re-reads the head
+/// and the tail of the previously recorded size, see `prefix_hash`.
+pub(crate) fn check_log(p: &Path, rule: &Rule, old: &BaselineRow, stat: &FileStat) -> Result<(LogVerdict, Option<(u64, String)>)> {
+    let Some(mode) = rule.log_mode else { return Ok((LogVerdict::Appended, None)) };
+    let next = Some(log_prefix(p, rule, stat.size)?);
+
+    if stat.size < old.stat.size {
+        return Ok((LogVerdict::Truncated, next));
+    }
+    if mode == LogMode::AppendOnly {
+        if let Some((len, h)) = &old.prefix {
+            let same = if *len == stat.size { next.as_ref().map(|n| &n.1) == Some(h) } else { prefix_hash(p, *len, rule.prefix_bytes)? == *h };
+            if !same {
+                return Ok((LogVerdict::Rewritten, next));
+            }
+        }
+    }
+    Ok((LogVerdict::Appended, next))
+}
+
+pub(crate) fn build_excluder(patterns: &[String]) -> Result<(globset::GlobSet, Vec<Glob>)> {
     let mut b = GlobSetBuilder::new();
     let mut raws = Vec::new();
     for p in patterns {
@@ -235,16 +997,70 @@ fn build_excluder(patterns: &[String]) -> Result<(globset::GlobSet, Vec<Glob>)>
     Ok((b.build()?, raws))
 }
 
-fn is_excluded(p: &Path, set: &globset::GlobSet) -> bool {
+pub(crate) fn is_excluded(p: &Path, set: &globset::GlobSet) -> bool {
     set.is_match(p)
 }
 
-fn hash_meta(p: &Path, cfg: &Config) -> Result<(String, u64, u64)> {
+fn mtime_secs(meta: &fs::Metadata) -> u64 {
+    meta.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .unwrap_or_default()
+        .as_secs()
+}
+
+type Indexed = (String, String, FileStat, Option<(u64, String)>);
+
+/// `fingerprint` plus the normalized path and log prefix, as run by the
+/// hashing workers. `None` for paths whose rule says `ignore`.
+fn hash_file(p: &Path, cfg: &Config, policy: &Policy, metrics: &Metrics) -> Result<Option<Indexed>> {
+    let norm = normalize_path(p);
+    let rule = policy.for_path(&norm);
+    if rule.ignored() { return Ok(None); }
+    let (hash, stat) = fingerprint_metered(p, rule, cfg, metrics, None)?;
+    let prefix = match rule.log_mode {
+        Some(_) => Some(log_prefix(p, rule, stat.size)?),
+        None => None,
+    };
+    Ok(Some((norm, hash, stat, prefix)))
+}
+
+/// Hash and stat as the rule asks for; without a content check the file is
+/// not read and the hash stays empty.
+pub(crate) fn fingerprint(p: &Path, rule: &Rule, cfg: &Config) -> Result<(String, FileStat)> {
+    fingerprint_paced(p, rule, cfg, None)
+}
+
+fn fingerprint_paced(p: &Path, rule: &Rule, cfg: &Config, limit: Option<&RateLimit>) -> Result<(String, FileStat)> {
+    if rule.hashes_content() {
+        hash_meta(p, rule.hash_alg(cfg), limit)
+    } else {
+        Ok((String::new(), FileStat::from_meta(&fs::metadata(p)?)))
+    }
+}
+
+/// `fingerprint` with hashing time, bytes and failures recorded in `metrics`;
+/// reads are paced by `limit` when given.
+fn fingerprint_metered(p: &Path, rule: &Rule, cfg: &Config, metrics: &Metrics, limit: Option<&RateLimit>) -> Result<(String, FileStat)> {
+    let start = Instant::now();
+    match fingerprint_paced(p, rule, cfg, limit) {
+        Ok(res) => {
+            if rule.hashes_content() {
+                metrics.observe_hash(start.elapsed(), res.1.size);
+            }
+            Ok(res)
+        }
+        Err(e) => {
+            metrics.hash_errors.inc();
+            Err(e)
+        }
+    }
+}
+
+fn hash_meta(p: &Path, alg: &str, limit: Option<&RateLimit>) -> Result<(String, FileStat)> {
     // choose hasher
-    let alg = cfg.hash_alg.to_lowercase();
+    let alg = alg.to_lowercase();
     let mut f = fs::File::open(p)?;
-    let size = f.metadata()?.len();
-    let mtime = f.metadata()?.modified()?.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let stat = FileStat::from_meta(&f.metadata()?);
 
     if alg == "sha256" {
         use sha2::{Sha256, Digest};
@@ -254,11 +1070,12 @@ fn hash_meta(p: &Path, cfg: &Config) -> Result<(String, u64, u64)> {
         loop {
             let n = f.read(&mut buf)?;
             if n == 0 { break; }
+            if let Some(l) = limit { l.take(n as u64); }
             hasher.update(&buf[..n]);
         }
         let res = hasher.finalize();
         let hash = format!("{:x}", res);
-        Ok((hash, size, mtime))
+        Ok((hash, stat))
     } else {
         // default blake3
         use std::io::{Read};
@@ -267,100 +1084,243 @@ fn hash_meta(p: &Path, cfg: &Config) -> Result<(String, u64, u64)> {
         loop {
             let n = f.read(&mut buf)?;
             if n == 0 { break; }
+            if let Some(l) = limit { l.take(n as u64); }
             hasher.update(&buf[..n]);
         }
         let res = hasher.finalize();
-        Ok((res.to_hex().to_string(), size, mtime))
+        Ok((res.to_hex().to_string(), stat))
+    }
+}
+
+fn perm_event(path: &str, old: &FileStat, new: &FileStat) -> AuditEvent {
+    AuditEvent {
+        old_mode: Some(old.mode_str()), new_mode: Some(new.mode_str()),
+        ..AuditEvent::new(Kind::PermChange, path.to_string())
+    }
+}
+
+fn owner_event(path: &str, old: &FileStat, new: &FileStat) -> AuditEvent {
+    AuditEvent {
+        old_owner: Some(old.owner_str()), new_owner: Some(new.owner_str()),
+        ..AuditEvent::new(Kind::OwnerChange, path.to_string())
     }
 }
 
-fn handle_upsert(conn: &rusqlite::Connection, p: &Path, jsonl: &mut fs::File, metrics: &Metrics, cfg: &Config) -> Result<()> {
+fn handle_upsert(conn: &rusqlite::Connection, p: &Path, out: &mut dyn EventSink, metrics: &Metrics, cfg: &Config, policy: &Policy) -> Result<()> {
     if !p.is_file() { return Ok(()); }
-    let (new_hash, size, mtime) = hash_meta(p, cfg)?;
     let norm = normalize_path(p);
+    let rule = policy.for_path(&norm);
+    if rule.ignored() { return Ok(()); }
+    let (new_hash, stat) = fingerprint_metered(p, rule, cfg, metrics, None)?;
 
-    let mut stmt = conn.prepare("SELECT hash FROM files WHERE path=?1")?;
-    let exists = stmt.exists(rusqlite::params![norm.clone()])?;
+    match load_row(conn, &norm)? {
+        Some(old) => {
+            upsert_row(conn, &norm, &new_hash, &stat)?;
+            let same_content = old.hash == new_hash;
 
-    let ts = now_ms();
-    if exists {
-        let old_hash: String = conn.query_row("SELECT hash FROM files WHERE path=?1", params![norm.clone()], |r| r.get(0))?;
-        conn.execute("UPDATE files SET hash=?1, size=?2, mtime=?3 WHERE path=?4",
-            params![new_hash.clone(), size as i64, mtime as i64, norm.clone()])?;
-
-        if old_hash != new_hash {
-            metrics.modified.inc();
-            write_jsonl(jsonl, AuditEvent {
-                ts, kind: "modify", path: norm, old_path: None,
-                old_hash: Some(old_hash), new_hash: Some(new_hash), size: Some(size)
+            if rule.log_mode.is_some() {
+                let (verdict, next) = check_log(p, rule, &old, &stat)?;
+                set_prefix(conn, &norm, next.as_ref())?;
+                if let Some(kind) = log_kind(verdict) {
+                    metrics.modified.inc();
+                    write_event(out, rule, AuditEvent {
+                        size: Some(stat.size), old_size: Some(old.stat.size),
+                        ..AuditEvent::new(kind, norm.clone())
+                    })?;
+                }
+            }
+            if rule.hashes_content() && old.hash != new_hash {
+                metrics.modified.inc();
+                write_event(out, rule, AuditEvent {
+                    old_hash: Some(old.hash), new_hash: Some(new_hash), size: Some(stat.size),
+                    ..AuditEvent::new(Kind::Modify, norm.clone())
+                })?;
+            }
+            if rule.checks(Check::Perm) && old.stat.perm_differs(&stat) {
+                write_event(out, rule, perm_event(&norm, &old.stat, &stat))?;
+            }
+            if rule.checks(Check::Owner) && old.stat.owner_differs(&stat) {
+                write_event(out, rule, owner_event(&norm, &old.stat, &stat))?;
+            }
+            // utime reaches us as a data event too; a real write is a `modify`
+            if rule.tracks_times() && same_content && old.stat.times_differ(&stat) {
+                write_event(out, rule, times_event(&norm, &old.stat, &stat))?;
+            }
+        }
+        None => {
+            upsert_row(conn, &norm, &new_hash, &stat)?;
+            if rule.log_mode.is_some() {
+                set_prefix(conn, &norm, Some(&log_prefix(p, rule, stat.size)?))?;
+            }
+            metrics.created.inc();
+            metrics.tracked_files.inc();
+            write_event(out, rule, AuditEvent {
+                new_hash: Some(new_hash), size: Some(stat.size), new_mode: Some(stat.mode_str()),
+                new_owner: Some(stat.owner_str()),
+                ..AuditEvent::new(Kind::Create, norm)
             })?;
         }
+    }
+    Ok(())
+}
+
+fn log_kind(v: LogVerdict) -> Option<Kind> {
+    match v {
+        LogVerdict::Appended => None,
+        LogVerdict::Truncated => Some(Kind::LogTruncated),
+        LogVerdict::Rewritten => Some(Kind::LogRewritten),
+    }
+}
+
+fn times_event(path: &str, old: &FileStat, new: &FileStat) -> AuditEvent {
+    AuditEvent {
+        old_mtime: Some(old.mtime), new_mtime: Some(new.mtime),
+        ..AuditEvent::new(Kind::TimesChange, path.to_string())
+    }
+}
+
+/// Handles chmod/chown/utime/xattr notifications by comparing stat fields
+/// against the baseline row. If the size, mtime or inode moved, a write may
+/// hide behind the event and the content is re-checked; otherwise only the
+/// access columns are updated, so the row never pairs fresh times with a hash
+/// nobody verified.
+fn handle_metadata(conn: &rusqlite::Connection, p: &Path, out: &mut dyn EventSink, metrics: &Metrics, cfg: &Config, policy: &Policy) -> Result<()> {
+    if !p.is_file() { return Ok(()); }
+    let norm = normalize_path(p);
+    let rule = policy.for_path(&norm);
+    if rule.ignored() { return Ok(()); }
+    let old = match load_row(conn, &norm)? {
+        Some(r) => r,
+        None => return handle_upsert(conn, p, out, metrics, cfg, policy),
+    };
+    let stat = FileStat::from_meta(&p.metadata()?);
+    if stat.size != old.stat.size {
+        return handle_upsert(conn, p, out, metrics, cfg, policy);
+    }
+    if stat == old.stat {
+        // xattr-only changes land here: they are not part of the baseline
+        debug!("metadata event without stat drift: {}", norm);
+        return Ok(());
+    }
+
+    if stat.mtime != old.stat.mtime || stat.inode != old.stat.inode {
+        let (hash, stat2) = fingerprint_metered(p, rule, cfg, metrics, None)?;
+        if hash != old.hash || stat2.size != old.stat.size {
+            return handle_upsert(conn, p, out, metrics, cfg, policy);
+        }
+        upsert_row(conn, &norm, &old.hash, &stat2)?;
     } else {
-        conn.execute("INSERT INTO files(path, hash, size, mtime) VALUES(?1, ?2, ?3, ?4)",
-            params![norm.clone(), new_hash.clone(), size as i64, mtime as i64])?;
-        metrics.created.inc();
-        metrics.tracked_files.inc();
-        write_jsonl(jsonl, AuditEvent {
-            ts, kind: "create", path: norm, old_path: None,
-            old_hash: None, new_hash: Some(new_hash), size: Some(size)
-        })?;
+        // ctime moved too, but the old one stays: a same-second write could
+        // hide behind it, and `scan --mode fast` then re-hashes the file
+        set_access(conn, &norm, &stat)?;
+    }
+    if rule.checks(Check::Perm) && old.stat.perm_differs(&stat) {
+        write_event(out, rule, perm_event(&norm, &old.stat, &stat))?;
+    }
+    if rule.checks(Check::Owner) && old.stat.owner_differs(&stat) {
+        write_event(out, rule, owner_event(&norm, &old.stat, &stat))?;
+    }
+    if rule.tracks_times() && old.stat.times_differ(&stat) {
+        write_event(out, rule, times_event(&norm, &old.stat, &stat))?;
     }
     Ok(())
 }
 
-fn handle_delete(conn: &rusqlite::Connection, p: &Path, jsonl: &mut fs::File, metrics: &Metrics) -> Result<()> {
+fn handle_delete(conn: &rusqlite::Connection, p: &Path, out: &mut dyn EventSink, metrics: &Metrics, policy: &Policy) -> Result<()> {
     let norm = normalize_path(p);
-    let ts = now_ms();
+    let rule = policy.for_path(&norm);
     let existed = conn.execute("DELETE FROM files WHERE path=?1", params![norm.clone()])?;
     if existed > 0 {
         metrics.deleted.inc();
         metrics.tracked_files.dec();
-        write_jsonl(jsonl, AuditEvent {
-            ts, kind: "delete", path: norm, old_path: None,
-            old_hash: None, new_hash: None, size: None
-        })?;
+        write_event(out, rule, AuditEvent::new(Kind::Delete, norm))?;
     }
     Ok(())
 }
 
-fn handle_rename(conn: &rusqlite::Connection, from: &Path, to: &Path, jsonl: &mut fs::File, _metrics: &Metrics, cfg: &Config) -> Result<()> {
+fn handle_rename(conn: &rusqlite::Connection, from: &Path, to: &Path, out: &mut dyn EventSink, metrics: &Metrics, cfg: &Config, policy: &Policy) -> Result<()> {
     let from_n = normalize_path(from);
     let to_n = normalize_path(to);
-    let ts = now_ms();
+    let rule = policy.for_path(&to_n);
+    // a move across an ignore rule is a delete or a create as far as the policy goes
+    if rule.ignored() {
+        return handle_delete(conn, from, out, metrics, policy);
+    }
+    if policy.for_path(&from_n).ignored() {
+        return handle_upsert(conn, to, out, metrics, cfg, policy);
+    }
 
     let affected = conn.execute("UPDATE files SET path=?1 WHERE path=?2", params![to_n.clone(), from_n.clone()])?;
     if affected == 0 {
         // if row doesn't exist (e.g., watcher started after), insert fresh
         if to.is_file() {
-            let (hash, size, mtime) = hash_meta(to, cfg)?;
-            conn.execute("INSERT OR REPLACE INTO files(path, hash, size, mtime) VALUES(?1, ?2, ?3, ?4)",
-                params![to_n.clone(), hash.clone(), size as i64, mtime as i64])?;
+            let (hash, stat) = fingerprint_metered(to, rule, cfg, metrics, None)?;
+            upsert_row(conn, &to_n, &hash, &stat)?;
         }
     }
-    let mut f = jsonl;
-    write_jsonl(&mut f, AuditEvent {
-        ts, kind: "rename", path: to_n, old_path: Some(from_n),
-        old_hash: None, new_hash: None, size: None
+    write_event(out, rule, AuditEvent {
+        old_path: Some(from_n),
+        ..AuditEvent::new(Kind::Rename, to_n)
     })?;
     Ok(())
 }
 
-fn write_jsonl(f: &mut fs::File, evt: AuditEvent<'_>) -> Result<()> {
-    let line = serde_json::to_string(&evt)? + "\n";
-    use std::io::Write;
-    f.write_all(line.as_bytes())?;
-    Ok(())
+fn write_event(out: &mut dyn EventSink, rule: &Rule, mut evt: AuditEvent) -> Result<()> {
+    evt.severity = rule.severity;
+    evt.rule = rule.name.clone();
+    evt.alert = rule.action == Action::Alert;
+    out.emit(&evt)
 }
 
-fn normalize_path(p: &Path) -> String {
+/// JSONL log (if any) plus the alert sinks configured in `cfg`.
+fn config_sinks(cfg: &Config, log: Option<impl EventSink + 'static>) -> Result<FanOut> {
+    let mut out = FanOut::new();
+    if let Some(log) = log {
+        out.push(log);
+    }
+    if let Some(hook) = Webhook::from_config(cfg)? {
+        out.push(hook);
+    }
+    if let Some(sys) = Syslog::from_config(cfg)? {
+        out.push(sys);
+    }
+    Ok(out)
+}
+
+/// Human-readable `scan` output when no diff file is requested.
+struct ScanPrinter;
+
+impl EventSink for ScanPrinter {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        match evt.kind {
+            Kind::PermChange => println!("PERM: {} {} -> {}", evt.path, opt(&evt.old_mode), opt(&evt.new_mode)),
+            Kind::OwnerChange => println!("OWNER: {} {} -> {}", evt.path, opt(&evt.old_owner), opt(&evt.new_owner)),
+            Kind::MtimeReset => println!("MTIME RESET: {}", evt.path),
+            kind => println!("{}: {}", kind.as_str().to_uppercase(), evt.path),
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn normalize_path(p: &Path) -> String {
     match dunce::canonicalize(p) {
         Ok(pp) => pp.to_string_lossy().to_string(),
         Err(_) => p.to_string_lossy().to_string(),
     }
 }
 
-fn debounce_hit(last: &mut HashMap<String, i128>, p: &Path, window_ms: i128) -> bool {
-    let key = normalize_path(p);
+/// Debounce is per path and class, so e.g. a chmod cannot swallow the write
+/// right after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum EventClass {
+    Name,
+    Meta,
+    Data,
+}
+
+fn debounce_hit(last: &mut HashMap<(String, EventClass), i128>, class: EventClass, p: &Path, window_ms: i128) -> bool {
+    let key = (normalize_path(p), class);
     let now = now_ms();
     if let Some(prev) = last.get(&key) {
         if now - *prev <= window_ms {
@@ -371,7 +1331,12 @@ fn debounce_hit(last: &mut HashMap<String, i128>, p: &Path, window_ms: i128) ->
     false
 }
 
-fn now_ms() -> i128 {
+/// Kind of the I/O error behind `e`, if there is one.
+fn io_kind(e: &anyhow::Error) -> Option<io::ErrorKind> {
+    e.chain().find_map(|c| c.downcast_ref::<io::Error>()).map(io::Error::kind)
+}
+
+pub(crate) fn now_ms() -> i128 {
     let now = OffsetDateTime::now_utc();
     now.unix_timestamp_nanos() / 1_000_000
 }
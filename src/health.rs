@@ -0,0 +1,86 @@
+use crate::config::Config;
+use crate::fim::now_ms;
+use crate::metrics::Metrics;
+use anyhow::{Context, Result};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+
+/// Longest the watch loop may go without coming round. It wakes at least
+/// every couple of seconds when idle; only hashing a huge file takes longer.
+const HEARTBEAT_STALE_SECS: i64 = 120;
+
+/// A watcher error this recent (e.g. an inotify queue overflow) fails the
+/// probe: events were lost, and a restart reconciles against the baseline.
+const WATCHER_ERROR_SECS: i64 = 60;
+
+/// How long the DB probe waits for the write lock.
+const DB_BUSY: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+struct HealthState {
+    cfg: Arc<Config>,
+    metrics: Metrics,
+}
+
+/// `GET /healthz` (liveness) and `GET /readyz` (readiness) for `watch`.
+/// Both answer JSON, with 503 when the check fails.
+pub fn router(cfg: Config, metrics: Metrics) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(HealthState { cfg: Arc::new(cfg), metrics })
+}
+
+async fn healthz(State(s): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let m = &s.metrics;
+    let now = (now_ms() / 1000) as i64;
+
+    let beat = m.heartbeat.get();
+    let beat_ok = beat > 0 && now - beat <= HEARTBEAT_STALE_SECS;
+
+    let last_error = m.watcher_last_error.get();
+    let error_age = (last_error > 0).then(|| now - last_error);
+    let watcher_ok = m.watcher_up.get() == 1 && error_age.is_none_or(|age| age > WATCHER_ERROR_SECS);
+
+    let cfg = s.cfg.clone();
+    let db = tokio::task::spawn_blocking(move || db_writable(&cfg))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|r| r);
+
+    let ok = beat_ok && watcher_ok && db.is_ok();
+    let body = json!({
+        "status": if ok { "ok" } else { "fail" },
+        "checks": {
+            "heartbeat": { "ok": beat_ok, "age_secs": (beat > 0).then(|| now - beat) },
+            "watcher": {
+                "ok": watcher_ok,
+                "up": m.watcher_up.get() == 1,
+                "errors": m.watcher_errors.get(),
+                "last_error_age_secs": error_age,
+            },
+            "db": { "ok": db.is_ok(), "error": db.err().map(|e| format!("{e:#}")) },
+        },
+    });
+    (if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }, Json(body))
+}
+
+async fn readyz(State(s): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let ready = s.metrics.ready.get() == 1;
+    let body = json!({
+        "ready": ready,
+        "tracked_files": s.metrics.tracked_files.get(),
+    });
+    (if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }, Json(body))
+}
+
+/// Takes and releases the write lock: fails on a read-only or locked DB.
+fn db_writable(cfg: &Config) -> Result<()> {
+    let conn = Connection::open_with_flags(&cfg.baseline_db, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .with_context(|| format!("open {}", cfg.baseline_db))?;
+    conn.busy_timeout(DB_BUSY)?;
+    conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;").context("baseline DB is not writable")?;
+    Ok(())
+}
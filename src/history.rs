@@ -0,0 +1,240 @@
+use crate::config::Config;
+use crate::fim::{self, FILE_COLUMNS};
+use crate::signing::{self, Keys};
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::SigningKey;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// One row of the `baselines` table.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub id: i64,
+    pub created_at: i64,
+    pub comment: Option<String>,
+    pub config_hash: String,
+    pub files: i64,
+    pub active: bool,
+    /// `watch` has changed `files` since this active generation was taken
+    pub dirty: bool,
+}
+
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(r#"
+    CREATE TABLE IF NOT EXISTS baselines (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      created_at INTEGER NOT NULL,
+      comment TEXT,
+      config_hash TEXT NOT NULL,
+      active INTEGER NOT NULL DEFAULT 0,
+      digest TEXT,
+      signature TEXT,
+      dirty INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS baseline_files (
+      generation INTEGER NOT NULL REFERENCES baselines(id),
+      path TEXT NOT NULL,
+      hash TEXT NOT NULL,
+      size INTEGER NOT NULL,
+      mtime INTEGER NOT NULL,
+      uid INTEGER NOT NULL,
+      gid INTEGER NOT NULL,
+      mode INTEGER NOT NULL,
+      inode INTEGER NOT NULL,
+      device INTEGER NOT NULL,
+      nlink INTEGER NOT NULL,
+      ctime INTEGER NOT NULL DEFAULT 0,
+      prefix_len INTEGER,
+      prefix_hash TEXT,
+      PRIMARY KEY (generation, path)
+    );
+    "#)?;
+    for (table, col, ty) in [
+        ("baseline_files", "ctime", "INTEGER NOT NULL DEFAULT 0"),
+        ("baseline_files", "prefix_len", "INTEGER"),
+        ("baseline_files", "prefix_hash", "TEXT"),
+        ("baselines", "digest", "TEXT"),
+        ("baselines", "signature", "TEXT"),
+        ("baselines", "dirty", "INTEGER NOT NULL DEFAULT 0"),
+    ] {
+        if !fim::has_column(conn, table, col)? {
+            conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {col} {ty}"))?;
+        }
+    }
+
+    // databases from before generations: keep the existing rows as generation 1
+    let gens: i64 = conn.query_row("SELECT COUNT(*) FROM baselines", [], |r| r.get(0))?;
+    let files: i64 = conn.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))?;
+    if gens == 0 && files > 0 {
+        let id = insert_generation(conn, Some("imported pre-generation baseline"), "")?;
+        snapshot(conn, id, None)?;
+        activate(conn, id)?;
+    }
+    Ok(())
+}
+
+/// Registers a new generation and returns its id. Does not activate it.
+pub(crate) fn insert_generation(conn: &Connection, comment: Option<&str>, config_hash: &str) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO baselines(created_at, comment, config_hash) VALUES(?1, ?2, ?3)",
+        params![time::OffsetDateTime::now_utc().unix_timestamp(), comment, config_hash],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Copies the working `files` table into `baseline_files` under `id` and
+/// records its digest, signed with `key` if there is one.
+pub(crate) fn snapshot(conn: &Connection, id: i64, key: Option<&SigningKey>) -> Result<()> {
+    conn.execute(
+        &format!("INSERT INTO baseline_files(generation, path, {FILE_COLUMNS}) SELECT ?1, path, {FILE_COLUMNS} FROM files"),
+        params![id],
+    )?;
+    let (digest, _) = signing::generation_digest(conn, id)?;
+    let signature = key.map(|k| signing::sign_digest(k, &digest));
+    conn.execute("UPDATE baselines SET digest=?1, signature=?2 WHERE id=?3", params![digest, signature, id])?;
+    Ok(())
+}
+
+/// Checks the rows of generation `id` against what `snapshot` recorded. With a
+/// verify key the digest must also carry a valid signature.
+fn verify_generation(conn: &Connection, id: i64, keys: &Keys) -> Result<()> {
+    let (digest, signature): (Option<String>, Option<String>) =
+        conn.query_row("SELECT digest, signature FROM baselines WHERE id=?1", params![id], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    let Some(digest) = digest else { bail!("no digest was recorded for generation {}", id) };
+    let (actual, rows) = signing::generation_digest(conn, id)?;
+    if actual != digest {
+        bail!("generation {} ({} rows) does not match the digest recorded when it was taken", id, rows);
+    }
+    if let Some(key) = &keys.verifying {
+        let signature = signature.ok_or_else(|| anyhow!("generation {} is not signed", id))?;
+        signing::verify_digest(key, &digest, &signature).with_context(|| format!("generation {}", id))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn activate(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("UPDATE baselines SET active = (id = ?1), dirty = 0", params![id])?;
+    Ok(())
+}
+
+/// Notes that `files` no longer matches the active generation's snapshot.
+pub(crate) fn mark_dirty(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE baselines SET dirty = 1 WHERE active = 1 AND dirty = 0", [])?;
+    Ok(())
+}
+
+/// Snapshots `files` as a new active generation, e.g. after `accept`.
+pub(crate) fn commit_generation(conn: &Connection, cfg: &Config, comment: &str, key: Option<&SigningKey>) -> Result<i64> {
+    let id = insert_generation(conn, Some(comment), &config_hash(cfg))?;
+    snapshot(conn, id, key)?;
+    activate(conn, id)?;
+    Ok(id)
+}
+
+pub(crate) fn config_hash(cfg: &Config) -> String {
+    let raw = serde_json::to_vec(cfg).unwrap_or_default();
+    blake3::hash(&raw).to_hex().to_string()
+}
+
+pub fn active_generation(conn: &Connection) -> Result<Option<i64>> {
+    Ok(conn.query_row("SELECT id FROM baselines WHERE active = 1", [], |r| r.get(0)).optional()?)
+}
+
+pub fn list_generations(cfg: &Config) -> Result<Vec<Generation>> {
+    let conn = fim::open_db(cfg)?;
+    let mut stmt = conn.prepare(
+        "SELECT b.id, b.created_at, b.comment, b.config_hash, b.active, b.dirty,
+                (SELECT COUNT(*) FROM baseline_files f WHERE f.generation = b.id)
+         FROM baselines b ORDER BY b.id")?;
+    let rows = stmt.query_map([], |r| Ok(Generation {
+        id: r.get(0)?,
+        created_at: r.get(1)?,
+        comment: r.get(2)?,
+        config_hash: r.get(3)?,
+        active: r.get::<_, i64>(4)? != 0,
+        dirty: r.get::<_, i64>(5)? != 0,
+        files: r.get(6)?,
+    }))?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Differences between two generations, keyed by path.
+#[derive(Debug, Default)]
+pub struct GenerationDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+pub fn diff_generations(cfg: &Config, from: i64, to: i64) -> Result<GenerationDiff> {
+    let conn = fim::open_db(cfg)?;
+    let old = load_generation(&conn, from)?;
+    let new = load_generation(&conn, to)?;
+
+    let mut diff = GenerationDiff::default();
+    for (path, row) in &new {
+        match old.get(path) {
+            None => diff.added.push(path.clone()),
+            Some(o) if o != row => diff.changed.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            diff.removed.push(path.clone());
+        }
+    }
+    Ok(diff)
+}
+
+/// Restores the working baseline from generation `id` and makes it active.
+/// Refused if the generation's rows no longer match its recorded digest.
+/// Changes `watch` made since the active generation was taken are kept as a
+/// generation of their own first.
+pub fn rollback(cfg: &Config, id: i64) -> Result<()> {
+    let keys = Keys::from_config(cfg)?;
+    let mut conn = fim::open_db(cfg)?;
+    let exists: bool = conn.query_row("SELECT COUNT(*) FROM baselines WHERE id=?1", params![id], |r| r.get::<_, i64>(0))? > 0;
+    if !exists {
+        bail!("no baseline generation {}", id);
+    }
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    verify_generation(&tx, id, &keys).context("refusing to roll back")?;
+    let dirty: bool = tx.query_row("SELECT COUNT(*) FROM baselines WHERE active = 1 AND dirty = 1", [], |r| r.get::<_, i64>(0))? > 0;
+    if dirty {
+        signing::check_baseline(&tx, cfg, &keys)?;
+        let kept = commit_generation(&tx, cfg, &format!("working baseline before rollback to {}", id), keys.signing.as_ref())?;
+        info!("Kept the modified working baseline as generation {}", kept);
+    }
+    tx.execute("DELETE FROM files", [])?;
+    let restored = tx.execute(
+        &format!("INSERT INTO files(path, {FILE_COLUMNS}) SELECT path, {FILE_COLUMNS} FROM baseline_files WHERE generation=?1"),
+        params![id],
+    )?;
+    activate(&tx, id)?;
+    tx.commit()?;
+    info!("Rolled back to generation {} ({} files)", id, restored);
+
+    signing::resign(&conn, cfg, &keys)?;
+    Ok(())
+}
+
+type Row = (String, i64, i64, i64, i64, i64);
+
+fn load_generation(conn: &Connection, id: i64) -> Result<BTreeMap<String, Row>> {
+    let mut stmt = conn.prepare(
+        "SELECT path, hash, size, mtime, uid, gid, mode FROM baseline_files WHERE generation=?1")?;
+    let rows = stmt.query_map(params![id], |r| Ok((r.get::<_, String>(0)?, (
+        r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?,
+    ))))?;
+    let map = rows.collect::<rusqlite::Result<BTreeMap<_, _>>>()?;
+    if map.is_empty() {
+        let known: bool = conn.query_row("SELECT COUNT(*) FROM baselines WHERE id=?1", params![id], |r| r.get::<_, i64>(0))? > 0;
+        if !known {
+            bail!("no baseline generation {}", id);
+        }
+    }
+    Ok(map)
+}
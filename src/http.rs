@@ -0,0 +1,190 @@
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use base64::Engine;
+use hyper_util::{rt::TokioIo, service::TowerToHyperService};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_rustls::{
+    rustls::{
+        self,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    },
+    TlsAcceptor,
+};
+use tracing::{debug, info, warn};
+
+/// Paths that stay open for probes even when auth is configured.
+const PUBLIC_PATHS: [&str; 2] = ["/healthz", "/readyz"];
+
+/// `[http]` section of the config: TLS, auth and the admin listener.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Serve the API (`/files`, `/events`, `/status`) here instead of on `metrics_bind`
+    #[serde(default)]
+    pub admin_bind: Option<String>,
+    /// PEM certificate chain; TLS is on when both cert and key are set
+    #[serde(default)]
+    pub tls_cert: Option<String>,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    #[serde(default)]
+    pub tls_key: Option<String>,
+    /// `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// `Authorization: Basic`, together with `basic_password`
+    #[serde(default)]
+    pub basic_user: Option<String>,
+    #[serde(default)]
+    pub basic_password: Option<String>,
+    /// Require auth for `/metrics` too; the API always requires it
+    #[serde(default = "default_true")]
+    pub auth_metrics: bool,
+}
+
+impl HttpConfig {
+    fn tls(&self) -> Result<Option<TlsAcceptor>> {
+        let (cert, key) = match (&self.tls_cert, &self.tls_key) {
+            (Some(c), Some(k)) => (c, k),
+            (None, None) => return Ok(None),
+            _ => bail!("[http] tls_cert and tls_key must be set together"),
+        };
+        let certs = CertificateDer::pem_file_iter(cert)
+            .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("read certificates {cert}"))?;
+        if certs.is_empty() {
+            bail!("no certificates in {cert}");
+        }
+        let key = PrivateKeyDer::from_pem_file(key).with_context(|| format!("read private key {key}"))?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("TLS certificate and key do not match")?;
+        Ok(Some(TlsAcceptor::from(Arc::new(config))))
+    }
+
+    fn auth(&self) -> Result<Option<Auth>> {
+        let basic = match (&self.basic_user, &self.basic_password) {
+            (Some(u), Some(p)) => Some(format!("{u}:{p}")),
+            (None, None) => None,
+            _ => bail!("[http] basic_user and basic_password must be set together"),
+        };
+        if self.bearer_token.is_none() && basic.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Auth { bearer: self.bearer_token.clone(), basic }))
+    }
+}
+
+/// Accepted credentials; either kind passes.
+#[derive(Debug, Clone)]
+struct Auth {
+    bearer: Option<String>,
+    /// `user:password`
+    basic: Option<String>,
+}
+
+impl Auth {
+    fn allows(&self, header: &str) -> bool {
+        if let (Some(tok), Some(want)) = (header.strip_prefix("Bearer "), &self.bearer) {
+            return same(tok.trim().as_bytes(), want.as_bytes());
+        }
+        if let (Some(b64), Some(want)) = (header.strip_prefix("Basic "), &self.basic) {
+            return base64::engine::general_purpose::STANDARD
+                .decode(b64.trim())
+                .is_ok_and(|got| same(&got, want.as_bytes()));
+        }
+        false
+    }
+}
+
+/// Starts the `watch` listeners: `metrics` on `metrics_bind`, and `api` either
+/// on `admin_bind` or next to `/metrics` when that is unset.
+pub async fn start(metrics_bind: &str, metrics: Router, api: Router, cfg: &HttpConfig) -> Result<Vec<JoinHandle<()>>> {
+    let tls = cfg.tls()?;
+    let auth = cfg.auth()?;
+    let guard = |app: Router| match &auth {
+        Some(a) => app.layer(middleware::from_fn_with_state(a.clone(), require_auth)),
+        None => app,
+    };
+    let metrics = if cfg.auth_metrics { guard(metrics) } else { metrics };
+    let mut handles = Vec::new();
+    match &cfg.admin_bind {
+        Some(admin) => {
+            handles.push(serve(metrics_bind, metrics, tls.clone()).await?);
+            handles.push(serve(admin, guard(api), tls).await?);
+        }
+        None => handles.push(serve(metrics_bind, metrics.merge(guard(api)), tls).await?),
+    }
+    Ok(handles)
+}
+
+/// Serves `app` on `bind`, over TLS when an acceptor is given.
+pub async fn serve(bind: &str, app: Router, tls: Option<TlsAcceptor>) -> Result<JoinHandle<()>> {
+    let addr: SocketAddr = bind.parse().with_context(|| format!("parse bind addr {bind}"))?;
+    let listener = TcpListener::bind(addr).await
+        .with_context(|| format!("bind http listener {}", addr))?;
+    let Some(tls) = tls else {
+        info!("http server listening on http://{}/", addr);
+        return Ok(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("http server failed: {e}");
+            }
+        }));
+    };
+    info!("http server listening on https://{}/", addr);
+    Ok(tokio::spawn(async move {
+        loop {
+            let (tcp, peer) = match listener.accept().await {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("accept on {addr} failed: {e}");
+                    continue;
+                }
+            };
+            let (tls, app) = (tls.clone(), app.clone());
+            tokio::spawn(async move {
+                let stream = match tls.accept(tcp).await {
+                    Ok(s) => s,
+                    Err(e) => return debug!("TLS handshake with {peer} failed: {e}"),
+                };
+                let conn = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), TowerToHyperService::new(app))
+                    .with_upgrades()
+                    .await;
+                if let Err(e) = conn {
+                    debug!("connection from {peer}: {e}");
+                }
+            });
+        }
+    }))
+}
+
+async fn require_auth(State(auth): State<Auth>, req: Request, next: Next) -> Response {
+    if PUBLIC_PATHS.contains(&req.uri().path()) {
+        return next.run(req).await;
+    }
+    let ok = req.headers().get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|h| auth.allows(h));
+    if ok {
+        return next.run(req).await;
+    }
+    let challenge = if auth.basic.is_some() { r#"Basic realm="sentra_fim""# } else { "Bearer" };
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, challenge)], "unauthorized\n").into_response()
+}
+
+/// Constant-time comparison, so the token cannot be guessed byte by byte.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn default_true() -> bool { true }
@@ -1,4 +1,20 @@
-
+pub mod api;
+pub mod accept;
+pub mod auditlog;
 pub mod config;
+pub mod event;
+pub mod eventdb;
+pub mod export;
 pub mod fim;
+pub mod health;
+pub mod history;
+pub mod http;
 pub mod metrics;
+pub mod policy;
+pub mod pool;
+pub mod query;
+pub mod rotate;
+pub mod signing;
+pub mod sink;
+pub mod syslog;
+pub mod webhook;
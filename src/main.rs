@@ -1,12 +1,11 @@
 
-mod config;
-mod fim;
-mod metrics;
+use sentra_fim::{accept, api, health, http, auditlog, config, event, fim, history, metrics, query};
 
 use clap::{Parser, Subcommand};
 use tracing::{Level};
 use tracing_subscriber::EnvFilter;
 use anyhow::Result;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "sentra_fim", about = "File Integrity Monitor with Prometheus & JSONL")]
@@ -24,6 +23,9 @@ enum Commands {
     Init {
         #[arg(short, long, default_value = "config.toml")]
         config: String,
+        /// Comment stored with the new baseline generation
+        #[arg(long)]
+        comment: Option<String>,
     },
     /// Watch filesystem, update baseline, emit JSONL and metrics
     Watch {
@@ -40,9 +42,82 @@ enum Commands {
         /// Optional JSONL diff output
         #[arg(long)]
         jsonl: Option<String>,
+        /// fast (trust matching stat data), full or paranoid
+        #[arg(long, default_value = "full")]
+        mode: fim::ScanMode,
+    },
+    /// Approve drift into the baseline for selected paths only
+    Accept {
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+        /// Paths or globs to accept
+        targets: Vec<String>,
+        /// Accept the entries of a diff written by `scan --jsonl`
+        #[arg(long)]
+        from_diff: Option<String>,
+        /// Approver name (defaults to $SUDO_USER / $USER)
+        #[arg(long)]
+        by: Option<String>,
+        /// Change ticket reference
+        #[arg(long)]
+        ticket: Option<String>,
+        #[arg(long)]
+        comment: Option<String>,
+    },
+    /// List, diff or roll back baseline generations
+    Generations {
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+        #[command(subcommand)]
+        action: GenerationCmd,
+    },
+    /// Search a JSONL audit log, including its rotated and compressed files
+    Query {
+        /// JSONL audit files, e.g. one per host; <file>.1, <file>.2.gz, ... are read too
+        #[arg(short, long, num_args = 1.., default_value = "events.jsonl")]
+        jsonl: Vec<PathBuf>,
+        /// RFC 3339, YYYY-MM-DD, unix time or an age like 24h / 7d
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        /// Event kind, repeatable (create, modify, delete, changed, ...)
+        #[arg(long)]
+        kind: Vec<event::EventKind>,
+        /// Glob over the file path
+        #[arg(long)]
+        path: Option<String>,
+        /// Prefix of the old or new content hash
+        #[arg(long)]
+        hash: Option<String>,
+        /// table or json (one object per line)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Follow the events with event counts per directory
+        #[arg(long)]
+        by_dir: bool,
+        /// Print only the per-directory counts, not the events
+        #[arg(long)]
+        summary_only: bool,
+    },
+    /// Verify the hash chain of a JSONL audit log
+    VerifyLog {
+        /// JSONL audit file to check
+        #[arg(short, long, default_value = "events.jsonl")]
+        jsonl: String,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum GenerationCmd {
+    /// List all baseline generations
+    List,
+    /// Show files that differ between two generations
+    Diff { from: i64, to: i64 },
+    /// Make an older generation the active baseline again
+    Rollback { id: i64 },
+}
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -56,22 +131,131 @@ async fn main() -> Result<()> {
         .init();
 
     match cli.command {
-        Commands::Init { config } => {
+        Commands::Init { config, comment } => {
             let cfg = config::Config::load(&config)?;
-            fim::build_baseline(&cfg)?;
-            println!("Baseline built at {}", cfg.baseline_db);
+            let id = fim::build_generation(&cfg, comment.as_deref())?;
+            println!("Baseline generation {} built at {}", id, cfg.baseline_db);
         }
         Commands::Watch { config, jsonl } => {
             let cfg = config::Config::load(&config)?;
             let prom = metrics::Metrics::try_new()?;
-            let http = metrics::serve_metrics(cfg.metrics_bind.clone(), prom.registry()).await?;
-            let _g = http; // keep server alive
+            let live = api::EventStream::new();
+            let api = api::router(cfg.clone(), jsonl.clone(), prom.clone(), live.clone());
+            let probes = metrics::metrics_router(prom.registry()).merge(health::router(cfg.clone(), prom.clone()));
+            let servers = http::start(&cfg.metrics_bind, probes, api,
+                &cfg.http.clone().unwrap_or_default()).await?;
+            let _g = servers; // keep servers alive
 
-            fim::watch_loop(cfg, jsonl, prom).await?;
+            fim::watch_loop(cfg, jsonl, prom, live).await?;
+        }
+        Commands::Scan { config, jsonl, mode } => {
+            let cfg = config::Config::load(&config)?;
+            fim::scan_with(&cfg, jsonl, mode)?;
         }
-        Commands::Scan { config, jsonl } => {
+        Commands::Accept { config, targets, from_diff, by, ticket, comment } => {
             let cfg = config::Config::load(&config)?;
-            fim::scan_diff(&cfg, jsonl)?;
+            if targets.is_empty() && from_diff.is_none() {
+                anyhow::bail!("nothing to accept: pass paths, globs or --from-diff");
+            }
+            let req = accept::AcceptRequest {
+                targets,
+                diff: from_diff,
+                approver: by.unwrap_or_else(accept::default_approver),
+                ticket,
+                comment,
+            };
+            let out = accept::accept(&cfg, &req)?;
+            for p in &out.updated { println!("ACCEPTED: {}", p); }
+            for p in &out.removed { println!("REMOVED: {}", p); }
+            for (p, why) in &out.skipped { println!("SKIPPED: {} ({})", p, why); }
+            println!("Summary -> accepted: {}, removed: {}, skipped: {}", out.updated.len(), out.removed.len(), out.skipped.len());
+            if let Some(g) = out.generation { println!("Baseline generation {} created", g); }
+        }
+        Commands::Generations { config, action } => {
+            let cfg = config::Config::load(&config)?;
+            match action {
+                GenerationCmd::List => {
+                    for g in history::list_generations(&cfg)? {
+                        println!("{}{:>5}  {}  files={:<8} config={}  {}{}",
+                            if g.active { "*" } else { " " }, g.id, g.created_at, g.files,
+                            &g.config_hash[..g.config_hash.len().min(12)],
+                            if g.dirty { "(modified by watch) " } else { "" }, g.comment.unwrap_or_default());
+                    }
+                }
+                GenerationCmd::Diff { from, to } => {
+                    let d = history::diff_generations(&cfg, from, to)?;
+                    for p in &d.added { println!("ADDED: {}", p); }
+                    for p in &d.changed { println!("CHANGED: {}", p); }
+                    for p in &d.removed { println!("REMOVED: {}", p); }
+                    println!("Summary -> added: {}, changed: {}, removed: {}", d.added.len(), d.changed.len(), d.removed.len());
+                }
+                GenerationCmd::Rollback { id } => {
+                    history::rollback(&cfg, id)?;
+                    println!("Active baseline is now generation {}", id);
+                }
+            }
+        }
+        Commands::Query { jsonl, since, until, kind, path, hash, format, by_dir, summary_only } => {
+            let q = query::Query {
+                since: since.as_deref().map(query::parse_time).transpose()?,
+                until: until.as_deref().map(query::parse_time).transpose()?,
+                kinds: kind,
+                path,
+                hash,
+            };
+            let events = query::run(&jsonl, &q)?;
+            let json = match format.as_str() {
+                "json" => true,
+                "table" => false,
+                other => anyhow::bail!("unknown format {other}: use table or json"),
+            };
+            if summary_only {
+                // nothing but the counts below
+            } else if json {
+                for e in &events {
+                    println!("{}", serde_json::to_string(e)?);
+                }
+            } else {
+                println!("{:<25} {:<14} {:<9} PATH", "TIME", "KIND", "SEVERITY");
+                for e in &events {
+                    let path = match &e.old_path {
+                        Some(old) => format!("{} -> {}", old, e.path),
+                        None => e.path.clone(),
+                    };
+                    println!("{:<25} {:<14} {:<9} {}", query::format_ts(e.ts), e.kind.as_str(), e.severity.as_str(), path);
+                }
+                println!("Summary -> {} events", events.len());
+            }
+            if by_dir || summary_only {
+                let mut counts: Vec<_> = query::counts_by_dir(&events).into_iter().collect();
+                counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                if !json && !summary_only {
+                    println!("\n{:>8}  DIR", "EVENTS");
+                }
+                for (dir, n) in counts {
+                    if json {
+                        println!("{}", serde_json::json!({ "dir": dir, "count": n }));
+                    } else {
+                        println!("{:>8}  {}", n, dir);
+                    }
+                }
+            }
+        }
+        Commands::VerifyLog { jsonl } => {
+            let report = auditlog::verify_log(&jsonl)?;
+            if let Some(b) = report.broken {
+                println!("BROKEN: {} line {}: expected prev {}, found {}",
+                    jsonl, b.line, b.expected, b.found.as_deref().unwrap_or("<none>"));
+                std::process::exit(2);
+            }
+            if !report.truncated.is_empty() {
+                for t in &report.truncated {
+                    println!("TRUNCATED: {} line {}: log was cut from {} bytes, lost chain head {}",
+                        jsonl, t.line, t.lost_size, t.lost_head);
+                }
+                std::process::exit(2);
+            }
+            println!("OK: {} records ({} unchained legacy)", report.records, report.legacy);
         }
     }
     Ok(())
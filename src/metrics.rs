@@ -1,11 +1,13 @@
 
+use crate::http;
 use anyhow::{Context, Result};
 use axum::{routing::get, Router};
-use prometheus::{Encoder, Registry, TextEncoder, IntCounter, IntGauge};
-use std::{net::SocketAddr, sync::Arc};
+use crate::fim::ScanSummary;
+use prometheus::{Encoder, Registry, TextEncoder, Gauge, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts};
+use std::path::Path;
+use std::{sync::Arc, time::Duration};
 use tokio::task::JoinHandle;
 use tokio::sync::RwLock;
-use tracing::info;
 
 #[derive(Clone)]
 pub struct Metrics {
@@ -14,6 +16,31 @@ pub struct Metrics {
     pub modified: IntCounter,
     pub deleted: IntCounter,
     pub tracked_files: IntGauge,
+    /// 1 while the filesystem watcher is delivering events
+    pub watcher_up: IntGauge,
+    pub watcher_errors: IntCounter,
+    /// Unix time of the last watcher error
+    pub watcher_last_error: IntGauge,
+    /// Unix time the watch loop last came round
+    pub heartbeat: IntGauge,
+    /// 1 once `watch` has caught up with the baseline and follows live events
+    pub ready: IntGauge,
+    /// Audit events by watch root and kind
+    pub events: IntCounterVec,
+    pub hash_seconds: Histogram,
+    pub hashed_bytes: IntCounter,
+    pub hash_errors: IntCounter,
+    /// Failed watch handlers (upsert, delete, rename, metadata, resign, reopen)
+    pub handler_errors: IntCounterVec,
+    /// Unix time of the last finished scan
+    pub last_scan: IntGauge,
+    /// Notifications received from the watcher but not handled yet
+    pub queue_depth: IntGauge,
+    /// Drift found by the last scan, by kind (added, changed, missing, ...)
+    pub scan_drift: IntGaugeVec,
+    /// Wall time of the last `init` or `scan` run
+    pub run_seconds: Gauge,
+    pub files_hashed: IntGauge,
 }
 
 impl Metrics {
@@ -27,6 +54,38 @@ impl Metrics {
             .context("create metric deleted")?;
         let tracked_files = IntGauge::new("fim_tracked_files", "Currently tracked files")
             .context("create metric tracked_files")?;
+        let watcher_up = IntGauge::new("fim_watcher_up", "Filesystem watcher is running")
+            .context("create metric watcher_up")?;
+        let watcher_errors = IntCounter::new("fim_watcher_errors_total", "Errors reported by the filesystem watcher")
+            .context("create metric watcher_errors")?;
+        let watcher_last_error = IntGauge::new("fim_watcher_last_error_timestamp_seconds", "Unix time of the last watcher error")
+            .context("create metric watcher_last_error")?;
+        let heartbeat = IntGauge::new("fim_heartbeat_timestamp_seconds", "Unix time the watch loop last came round")
+            .context("create metric heartbeat")?;
+        let ready = IntGauge::new("fim_ready", "Watch is following live events")
+            .context("create metric ready")?;
+        let events = IntCounterVec::new(Opts::new("fim_events_total", "Audit events by watch root and kind"), &["root", "kind"])
+            .context("create metric events")?;
+        let hash_seconds = Histogram::with_opts(
+            HistogramOpts::new("fim_hash_duration_seconds", "Time to hash one file")
+                .buckets(prometheus::exponential_buckets(0.0005, 4.0, 9)?))
+            .context("create metric hash_seconds")?;
+        let hashed_bytes = IntCounter::new("fim_hashed_bytes_total", "Bytes read for hashing")
+            .context("create metric hashed_bytes")?;
+        let hash_errors = IntCounter::new("fim_hash_errors_total", "Files that could not be hashed")
+            .context("create metric hash_errors")?;
+        let handler_errors = IntCounterVec::new(Opts::new("fim_handler_errors_total", "Failed watch handlers"), &["handler"])
+            .context("create metric handler_errors")?;
+        let last_scan = IntGauge::new("fim_last_scan_timestamp_seconds", "Unix time of the last finished scan")
+            .context("create metric last_scan")?;
+        let queue_depth = IntGauge::new("fim_watcher_queue_depth", "Watcher notifications waiting to be handled")
+            .context("create metric queue_depth")?;
+        let scan_drift = IntGaugeVec::new(Opts::new("fim_scan_drift_files", "Drift found by the last scan"), &["kind"])
+            .context("create metric scan_drift")?;
+        let run_seconds = Gauge::new("fim_run_duration_seconds", "Wall time of the last init or scan")
+            .context("create metric run_seconds")?;
+        let files_hashed = IntGauge::new("fim_run_files_hashed", "Files hashed by the last init or scan")
+            .context("create metric files_hashed")?;
 
         registry.register(Box::new(created.clone()))
             .context("register created")?;
@@ -36,8 +95,83 @@ impl Metrics {
             .context("register deleted")?;
         registry.register(Box::new(tracked_files.clone()))
             .context("register tracked_files")?;
+        registry.register(Box::new(watcher_up.clone()))
+            .context("register watcher_up")?;
+        registry.register(Box::new(watcher_errors.clone()))
+            .context("register watcher_errors")?;
+        registry.register(Box::new(watcher_last_error.clone()))
+            .context("register watcher_last_error")?;
+        registry.register(Box::new(heartbeat.clone()))
+            .context("register heartbeat")?;
+        registry.register(Box::new(ready.clone()))
+            .context("register ready")?;
+        registry.register(Box::new(events.clone()))
+            .context("register events")?;
+        registry.register(Box::new(hash_seconds.clone()))
+            .context("register hash_seconds")?;
+        registry.register(Box::new(hashed_bytes.clone()))
+            .context("register hashed_bytes")?;
+        registry.register(Box::new(hash_errors.clone()))
+            .context("register hash_errors")?;
+        registry.register(Box::new(handler_errors.clone()))
+            .context("register handler_errors")?;
+        registry.register(Box::new(last_scan.clone()))
+            .context("register last_scan")?;
+        registry.register(Box::new(queue_depth.clone()))
+            .context("register queue_depth")?;
+        registry.register(Box::new(scan_drift.clone()))
+            .context("register scan_drift")?;
+        registry.register(Box::new(run_seconds.clone()))
+            .context("register run_seconds")?;
+        registry.register(Box::new(files_hashed.clone()))
+            .context("register files_hashed")?;
 
-        Ok(Self { registry, created, modified, deleted, tracked_files })
+        Ok(Self {
+            registry, created, modified, deleted, tracked_files, watcher_up, watcher_errors,
+            watcher_last_error, heartbeat, ready,
+            events, hash_seconds, hashed_bytes, hash_errors, handler_errors, last_scan, queue_depth,
+            scan_drift, run_seconds, files_hashed,
+        })
+    }
+
+    /// Counts one event under the longest of `roots` containing `path`.
+    pub fn count_event(&self, roots: &[String], path: &str, kind: &str) {
+        let root = roots.iter()
+            .filter(|r| Path::new(path).starts_with(r))
+            .max_by_key(|r| r.len())
+            .map_or("", |r| r.as_str());
+        self.events.with_label_values(&[root, kind]).inc();
+    }
+
+    pub fn observe_hash(&self, took: Duration, bytes: u64) {
+        self.hash_seconds.observe(took.as_secs_f64());
+        self.hashed_bytes.inc_by(bytes);
+    }
+
+    pub fn handler_error(&self, handler: &str) {
+        self.handler_errors.with_label_values(&[handler]).inc();
+    }
+
+    /// Drift counts of a finished scan.
+    pub fn record_scan(&self, sum: &ScanSummary) {
+        for (kind, n) in [
+            ("added", sum.added),
+            ("changed", sum.changed),
+            ("missing", sum.missing),
+            ("perm", sum.perm),
+            ("owner", sum.owner),
+            ("mtime_reset", sum.mtime_reset),
+            ("log_truncated", sum.log_truncated),
+            ("log_rewritten", sum.log_rewritten),
+        ] {
+            self.scan_drift.with_label_values(&[kind]).set(n as i64);
+        }
+    }
+
+    /// Duration and hashed files of a finished `init` or `scan`.
+    pub fn record_run(&self, took: Duration, hashed: usize) {
+        self.run_seconds.set(took.as_secs_f64());
+        self.files_hashed.set(hashed as i64);
     }
 
     pub fn registry(&self) -> Registry {
@@ -45,25 +179,20 @@ impl Metrics {
     }
 }
 
+/// `/metrics` alone over plain HTTP. Probes need the watch state: mount
+/// `health::router` next to it, as `watch` does through `http::start`.
 pub async fn serve_metrics(bind: String, registry: Registry) -> Result<JoinHandle<()>> {
+    http::serve(&bind, metrics_router(registry), None).await
+}
+
+/// `/metrics` alone; `watch` adds `health::router` and the API, see `http::start`.
+pub fn metrics_router(registry: Registry) -> Router {
     let reg = Arc::new(RwLock::new(registry));
-    let app = Router::new()
+    Router::new()
         .route("/metrics", get({
             let reg = reg.clone();
             move || metrics_handler(reg.clone())
         }))
-        .route("/healthz", get(|| async { "ok" }));
-
-    let addr: SocketAddr = bind.parse().context("parse metrics bind addr")?;
-    info!("metrics server listening on http://{}/ (paths: /metrics, /healthz)", addr);
-    let handle = tokio::spawn(async move {
-        if let Err(e) = axum::Server::bind(&addr)
-            .serve(app.into_make_service())
-            .await {
-            eprintln!("metrics server failed: {e}");
-        }
-    });
-    Ok(handle)
 }
 
 async fn metrics_handler(registry: Arc<RwLock<Registry>>) -> String {
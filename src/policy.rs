@@ -0,0 +1,159 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Attributes a rule can ask to be compared against the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// Content hash and size.
+    Content,
+    /// Mode bits.
+    Perm,
+    /// uid/gid.
+    Owner,
+    /// mtime.
+    Times,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Record the event and mark it for alerting.
+    #[default]
+    Alert,
+    /// Record the event only.
+    Log,
+    /// Do not track matching paths at all.
+    Ignore,
+}
+
+/// Growth policy for files that are expected to change constantly (logs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogMode {
+    /// Size may not shrink, and the first and the last `prefix_bytes` of the
+    /// previously seen content may not change (edits in between are missed).
+    AppendOnly,
+    /// Size may not shrink.
+    GrowOnly,
+}
+
+/// One `[[rule]]` section of the config. The first rule whose glob matches
+/// the normalized path wins; unmatched paths get `Rule::default()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub glob: String,
+    #[serde(default = "all_checks")]
+    pub check: Vec<Check>,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default)]
+    pub action: Action,
+    /// Overrides the global `hash_alg` for matching files
+    #[serde(default)]
+    pub hash_alg: Option<String>,
+    /// Replaces content hashing with truncate/rewrite detection
+    #[serde(default)]
+    pub log_mode: Option<LogMode>,
+    /// Bytes `append_only` fingerprints at the head and at the end of the old content
+    #[serde(default = "default_prefix_bytes")]
+    pub prefix_bytes: u64,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            name: None,
+            glob: "**".to_string(),
+            check: all_checks(),
+            severity: Severity::default(),
+            action: Action::default(),
+            hash_alg: None,
+            log_mode: None,
+            prefix_bytes: default_prefix_bytes(),
+        }
+    }
+}
+
+impl Rule {
+    pub fn checks(&self, c: Check) -> bool {
+        self.check.contains(&c)
+    }
+
+    /// Content is hashed unless the rule does not ask for it or treats the
+    /// file as a log.
+    pub fn hashes_content(&self) -> bool {
+        self.checks(Check::Content) && self.log_mode.is_none()
+    }
+
+    /// mtime drift is expected on logs, so `times` only applies elsewhere.
+    pub fn tracks_times(&self) -> bool {
+        self.checks(Check::Times) && self.log_mode.is_none()
+    }
+
+    pub fn ignored(&self) -> bool {
+        self.action == Action::Ignore
+    }
+
+    pub fn hash_alg<'a>(&'a self, cfg: &'a Config) -> &'a str {
+        self.hash_alg.as_deref().unwrap_or(&cfg.hash_alg)
+    }
+}
+
+fn default_prefix_bytes() -> u64 { 4096 }
+
+fn all_checks() -> Vec<Check> {
+    vec![Check::Content, Check::Perm, Check::Owner, Check::Times]
+}
+
+/// Compiled rule set.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    set: GlobSet,
+    rules: Vec<Rule>,
+    fallback: Rule,
+}
+
+impl Policy {
+    pub fn from_config(cfg: &Config) -> Result<Self> {
+        let mut b = GlobSetBuilder::new();
+        for r in &cfg.rules {
+            b.add(Glob::new(&r.glob).with_context(|| format!("invalid rule glob {}", r.glob))?);
+        }
+        Ok(Self { set: b.build()?, rules: cfg.rules.clone(), fallback: Rule::default() })
+    }
+
+    /// First rule matching `path` (a normalized path string).
+    pub fn for_path(&self, path: &str) -> &Rule {
+        self.set.matches(path).into_iter().min()
+            .map(|i| &self.rules[i])
+            .unwrap_or(&self.fallback)
+    }
+}
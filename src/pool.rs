@@ -0,0 +1,157 @@
+use crate::config::Config;
+use crate::fim;
+use anyhow::Result;
+use globset::GlobSet;
+use std::{
+    collections::BTreeMap,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use walkdir::WalkDir;
+
+/// Number of hashing workers for `cfg`; `hash_threads = 0` means one per CPU.
+pub fn worker_count(cfg: &Config) -> usize {
+    if cfg.hash_threads > 0 {
+        return cfg.hash_threads;
+    }
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Walks the watch roots and runs `work` on every non-excluded file using a
+/// bounded pool of worker threads. Results are handed to `sink` on the
+/// calling thread in walk order, so the caller can keep a single SQLite
+/// writer. Returns the number of files processed.
+pub(crate) fn walk_parallel<T, W, S>(cfg: &Config, exclude: &GlobSet, work: W, sink: S) -> Result<usize>
+where
+    T: Send,
+    W: Fn(&Path) -> T + Sync,
+    S: FnMut(PathBuf, T) -> Result<()>,
+{
+    walk_parallel_with(cfg, exclude, || (), |_, p| work(p), sink)
+}
+
+/// Like `walk_parallel`, but every worker first builds its own state with
+/// `init` (e.g. a read-only SQLite connection) and passes it to `work`.
+pub(crate) fn walk_parallel_with<St, T, I, W, S>(cfg: &Config, exclude: &GlobSet, init: I, work: W, mut sink: S) -> Result<usize>
+where
+    T: Send,
+    I: Fn() -> St + Sync,
+    W: Fn(&mut St, &Path) -> T + Sync,
+    S: FnMut(PathBuf, T) -> Result<()>,
+{
+    let threads = worker_count(cfg);
+    let (path_tx, path_rx) = mpsc::sync_channel::<(usize, PathBuf)>(threads * 4);
+    let (res_tx, res_rx) = mpsc::sync_channel::<(usize, PathBuf, T)>(threads * 4);
+    let path_rx = Arc::new(Mutex::new(path_rx));
+    let mut progress = Progress::new();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut seq = 0usize;
+            for root in &cfg.watch_paths {
+                for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+                    let p = entry.path();
+                    if !p.is_file() || fim::is_excluded(p, exclude) { continue; }
+                    // receivers gone: the writer bailed out
+                    if path_tx.send((seq, p.to_path_buf())).is_err() { return; }
+                    seq += 1;
+                }
+            }
+        });
+        for _ in 0..threads {
+            let rx = Arc::clone(&path_rx);
+            let tx = res_tx.clone();
+            let (init, work) = (&init, &work);
+            s.spawn(move || {
+                let mut state = init();
+                loop {
+                    let next = rx.lock().map(|r| r.recv());
+                    let Ok(Ok((seq, p))) = next else { return };
+                    let out = work(&mut state, &p);
+                    if tx.send((seq, p, out)).is_err() { return; }
+                }
+            });
+        }
+        // only workers may hold these, so a failing sink unwinds the pipeline
+        drop(res_tx);
+        drop(path_rx);
+        let res_rx = res_rx;
+
+        // reorder so results reach the writer in walk order
+        let mut pending = BTreeMap::new();
+        let mut next = 0usize;
+        for (seq, p, out) in res_rx.iter() {
+            pending.insert(seq, (p, out));
+            while let Some((p, out)) = pending.remove(&next) {
+                sink(p, out)?;
+                next += 1;
+                progress.tick(next);
+            }
+        }
+        progress.finish(next);
+        Ok(next)
+    })
+}
+
+/// Single-line counter on stderr, only when stderr is a terminal.
+struct Progress {
+    enabled: bool,
+    start: Instant,
+    last: Instant,
+}
+
+impl Progress {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self { enabled: std::io::stderr().is_terminal(), start: now, last: now }
+    }
+
+    fn tick(&mut self, done: usize) {
+        if !self.enabled || self.last.elapsed() < Duration::from_millis(500) { return; }
+        self.last = Instant::now();
+        let rate = done as f64 / self.start.elapsed().as_secs_f64().max(0.001);
+        let mut err = std::io::stderr().lock();
+        let _ = write!(err, "\rhashed {} files ({:.0}/s)", done, rate);
+        let _ = err.flush();
+    }
+
+    fn finish(&self, done: usize) {
+        if !self.enabled || self.start.elapsed() < Duration::from_millis(500) { return; }
+        eprintln!("\rhashed {} files in {:.1}s", done, self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// Paces reads shared by all hashing workers to `bytes_per_sec` on average.
+pub struct RateLimit {
+    bytes_per_sec: u64,
+    /// window start and bytes read since
+    window: Mutex<(Instant, u64)>,
+}
+
+impl RateLimit {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self { bytes_per_sec: bytes_per_sec.max(1), window: Mutex::new((Instant::now(), 0)) }
+    }
+
+    /// Accounts `n` bytes just read and sleeps until they fit the budget.
+    pub fn take(&self, n: u64) {
+        let due = {
+            let mut w = self.window.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            // idle time (stat-only paths, small files) buys at most a second of burst
+            let budget = w.0 + Duration::from_secs_f64(w.1 as f64 / self.bytes_per_sec as f64);
+            if budget + Duration::from_secs(1) < now {
+                *w = (now.checked_sub(Duration::from_secs(1)).unwrap_or(now), 0);
+            }
+            w.1 += n;
+            w.0 + Duration::from_secs_f64(w.1 as f64 / self.bytes_per_sec as f64)
+        };
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::event::{AuditEvent, EventKind, EventReader};
+use crate::fim::now_ms;
+use crate::rotate;
+use anyhow::{bail, Context, Result};
+use globset::{Glob, GlobMatcher};
+use std::{collections::{BTreeMap, VecDeque}, path::{Path, PathBuf}};
+use time::{format_description::well_known::Rfc3339, Date, OffsetDateTime};
+
+/// Filters for `query`; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Unix ms, inclusive
+    pub since: Option<i64>,
+    /// Unix ms, exclusive
+    pub until: Option<i64>,
+    pub kinds: Vec<EventKind>,
+    /// Glob over `path` (and `old_path` of renames)
+    pub path: Option<String>,
+    /// Prefix of `old_hash` or `new_hash`
+    pub hash: Option<String>,
+}
+
+/// Reads each of `logs` and all of its rotations and returns the matching events,
+/// oldest first; events from several logs are merged by timestamp.
+pub fn run(logs: &[PathBuf], q: &Query) -> Result<Vec<AuditEvent>> {
+    let glob = match &q.path {
+        Some(g) => Some(Glob::new(g).with_context(|| format!("invalid glob {g}"))?.compile_matcher()),
+        None => None,
+    };
+    let mut out = Vec::new();
+    for log in logs {
+        let log = log.to_string_lossy();
+        let files = rotate::rotated_files(&log);
+        if files.is_empty() {
+            bail!("no audit log at {log}");
+        }
+        for file in files.iter().rev() {
+            scan_file(file, q, glob.as_ref(), &mut out)?;
+        }
+    }
+    if logs.len() > 1 {
+        out.sort_by_key(|e| e.ts);
+    }
+    Ok(out)
+}
+
+fn scan_file(file: &str, q: &Query, glob: Option<&GlobMatcher>, out: &mut Vec<AuditEvent>) -> Result<()> {
+    for evt in EventReader::new(rotate::open_any(file)?) {
+        let evt = evt.with_context(|| format!("read {file}"))?;
+        if q.since.is_some_and(|t| evt.ts < t) || q.until.is_some_and(|t| evt.ts >= t) {
+            continue;
+        }
+        if !q.kinds.is_empty() && !q.kinds.contains(&evt.kind) {
+            continue;
+        }
+        if let Some(g) = glob {
+            if !g.is_match(&evt.path) && !evt.old_path.as_ref().is_some_and(|p| g.is_match(p)) {
+                continue;
+            }
+        }
+        if let Some(h) = &q.hash {
+            let hit = |x: &Option<String>| x.as_ref().is_some_and(|x| x.starts_with(h.as_str()));
+            if !hit(&evt.old_hash) && !hit(&evt.new_hash) {
+                continue;
+            }
+        }
+        out.push(evt);
+    }
+    Ok(())
+}
+
+/// The last `limit` events at or after `since` in `log` and its rotations,
+/// oldest first. Files are read newest first; older rotations are not opened
+/// once `limit` events are collected or a file ends before `since`.
+pub fn recent(log: &str, since: i64, limit: usize) -> Result<Vec<AuditEvent>> {
+    let mut out = VecDeque::new();
+    for file in rotate::rotated_files(log) {
+        let want = limit - out.len();
+        if want == 0 {
+            break;
+        }
+        let mut tail = VecDeque::with_capacity(want.min(1024));
+        let mut last_ts = None;
+        for evt in EventReader::new(rotate::open_any(&file)?) {
+            let evt = evt.with_context(|| format!("read {file}"))?;
+            last_ts = Some(evt.ts);
+            if evt.ts < since {
+                continue;
+            }
+            if tail.len() == want {
+                tail.pop_front();
+            }
+            tail.push_back(evt);
+        }
+        for evt in tail.into_iter().rev() {
+            out.push_front(evt);
+        }
+        if last_ts.is_some_and(|t| t < since) {
+            break;
+        }
+    }
+    Ok(out.into())
+}
+
+/// Number of events per parent directory.
+pub fn counts_by_dir(events: &[AuditEvent]) -> BTreeMap<String, usize> {
+    let mut out = BTreeMap::new();
+    for e in events {
+        let dir = Path::new(&e.path).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        *out.entry(dir).or_insert(0) += 1;
+    }
+    out
+}
+
+/// Accepts RFC 3339, `YYYY-MM-DD` (UTC midnight), unix seconds or ms,
+/// and relative ages like `30m`, `24h`, `7d` (that long ago).
+pub fn parse_time(s: &str) -> Result<i64> {
+    let s = s.trim();
+    if let Ok(n) = s.parse::<i64>() {
+        // anything below 10^11 cannot be ms since 1973
+        return Ok(if n < 100_000_000_000 { n * 1000 } else { n });
+    }
+    if let Some((num, unit)) = s.split_at_checked(s.len().saturating_sub(1)) {
+        if let Ok(n) = num.parse::<i64>() {
+            let ms = match unit {
+                "s" => 1_000,
+                "m" => 60_000,
+                "h" => 3_600_000,
+                "d" => 86_400_000,
+                _ => bail!("unknown time unit in {s}"),
+            };
+            return Ok(now_ms() as i64 - n * ms);
+        }
+    }
+    if let Ok(t) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok((t.unix_timestamp_nanos() / 1_000_000) as i64);
+    }
+    let fmt = time::macros::format_description!("[year]-[month]-[day]");
+    if let Ok(d) = Date::parse(s, fmt) {
+        return Ok(d.midnight().assume_utc().unix_timestamp() * 1000);
+    }
+    bail!("cannot parse time {s:?}: use RFC 3339, YYYY-MM-DD, unix time or 30m/24h/7d")
+}
+
+/// RFC 3339 in UTC with ms, for table output.
+pub fn format_ts(ms: i64) -> String {
+    let fmt = time::macros::format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z");
+    OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000)
+        .ok()
+        .and_then(|t| t.format(fmt).ok())
+        .unwrap_or_else(|| ms.to_string())
+}
@@ -0,0 +1,161 @@
+use crate::auditlog::AuditLog;
+use crate::event::{AuditEvent, EventReader};
+use crate::fim::now_ms;
+use crate::sink::EventSink;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use tracing::{info, warn};
+
+/// `[rotation]` section of the config; applies to the `watch --jsonl` log.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RotationConfig {
+    /// Rotate once the log reaches this many bytes
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Rotate once the first record is this old
+    #[serde(default)]
+    pub max_age_hours: Option<u64>,
+    /// Rotated files to keep: `<log>.1` (newest) .. `<log>.N`
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+    /// "gzip" or "zstd"; rotated files stay plain if unset
+    #[serde(default)]
+    pub compress: Option<String>,
+}
+
+/// Extensions a rotated file may carry, see `rotated_files`.
+const EXTS: [&str; 3] = ["", ".gz", ".zst"];
+
+/// JSONL audit log that rotates itself by size and/or age. Every file
+/// starts its own hash chain, so each one passes `verify-log` alone.
+pub struct RotatingLog {
+    log: AuditLog,
+    cfg: RotationConfig,
+    /// ts of the first record in the current file
+    started: Option<i64>,
+}
+
+impl RotatingLog {
+    pub fn new(log: AuditLog, cfg: RotationConfig) -> Result<Self> {
+        if let Some(c) = &cfg.compress {
+            if c != "gzip" && c != "zstd" {
+                bail!("unknown rotation compress: {c} (expected gzip or zstd)");
+            }
+        }
+        let started = first_ts(log.path());
+        Ok(Self { log, cfg, started })
+    }
+
+    fn due(&self) -> bool {
+        if self.log.is_empty() {
+            return false;
+        }
+        let by_size = self.cfg.max_bytes.is_some_and(|max| self.log.len() >= max);
+        let by_age = match (self.cfg.max_age_hours, self.started) {
+            (Some(h), Some(t)) => now_ms() as i64 - t >= h as i64 * 3_600_000,
+            _ => false,
+        };
+        by_size || by_age
+    }
+
+    /// `<log>` becomes `<log>.1`, older files shift up, the ones beyond `keep` are deleted.
+    pub fn rotate(&mut self) -> Result<()> {
+        let path = self.log.path().to_string();
+        for ext in EXTS {
+            let victim = format!("{}.{}{}", path, self.cfg.keep.max(1), ext);
+            remove_if_exists(&victim)?;
+        }
+        for n in (1..self.cfg.keep.max(1)).rev() {
+            for ext in EXTS {
+                let from = format!("{}.{}{}", path, n, ext);
+                if Path::new(&from).exists() {
+                    fs::rename(&from, format!("{}.{}{}", path, n + 1, ext))?;
+                }
+            }
+        }
+        let first = format!("{}.1", path);
+        fs::rename(&path, &first).with_context(|| format!("rotate {}", path))?;
+        self.log.reopen()?;
+        self.started = None;
+        if let Some(c) = &self.cfg.compress {
+            compress(&first, c)?;
+        }
+        info!("rotated audit log {}", path);
+        Ok(())
+    }
+}
+
+impl EventSink for RotatingLog {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        if self.due() {
+            if let Err(e) = self.rotate() {
+                warn!("log rotation failed: {e}");
+            }
+        }
+        self.started.get_or_insert(evt.ts);
+        self.log.emit(evt)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        self.log.reopen()?;
+        self.started = first_ts(self.log.path());
+        Ok(())
+    }
+}
+
+/// The live log followed by its rotated files, newest first.
+pub fn rotated_files(path: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    if Path::new(path).exists() {
+        out.push(path.to_string());
+    }
+    for n in 1.. {
+        match EXTS.iter().map(|ext| format!("{}.{}{}", path, n, ext)).find(|p| Path::new(p).exists()) {
+            Some(p) => out.push(p),
+            None => break,
+        }
+    }
+    out
+}
+
+/// Opens a plain, `.gz` or `.zst` log for reading.
+pub fn open_any(path: &str) -> Result<Box<dyn io::Read>> {
+    let f = fs::File::open(path).with_context(|| format!("open {}", path))?;
+    Ok(if path.ends_with(".gz") {
+        Box::new(flate2::read::MultiGzDecoder::new(f))
+    } else if path.ends_with(".zst") {
+        Box::new(zstd::stream::read::Decoder::new(f)?)
+    } else {
+        Box::new(f)
+    })
+}
+
+fn compress(path: &str, alg: &str) -> Result<()> {
+    let mut src = fs::File::open(path)?;
+    let dst_path = format!("{}{}", path, if alg == "gzip" { ".gz" } else { ".zst" });
+    let dst = fs::File::create(&dst_path)?;
+    if alg == "gzip" {
+        let mut enc = flate2::write::GzEncoder::new(dst, flate2::Compression::default());
+        io::copy(&mut src, &mut enc)?;
+        enc.finish()?.sync_all()?;
+    } else {
+        zstd::stream::copy_encode(&mut src, &dst, 0)?;
+        dst.sync_all()?;
+    }
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+fn first_ts(path: &str) -> Option<i64> {
+    EventReader::open(path).ok()?.next()?.ok().map(|e| e.ts)
+}
+
+fn remove_if_exists(p: &str) -> Result<()> {
+    match fs::remove_file(p) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn default_keep() -> usize { 7 }
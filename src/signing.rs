@@ -0,0 +1,186 @@
+use crate::config::Config;
+use crate::fim::FILE_COLUMNS;
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::{types::ValueRef, Connection, Params};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tracing::{error, info, warn};
+
+/// Detached signature over the contents of the `files` table, stored next to
+/// the database as `<baseline_db>.sig`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: u64,
+    pub digest: String,
+    pub public_key: String,
+    pub signature: String,
+    pub signed_at: i64,
+}
+
+/// What `scan` and `watch` do when the baseline does not verify.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignaturePolicy {
+    /// Refuse to use the baseline.
+    #[default]
+    Enforce,
+    /// Log the failure loudly and carry on.
+    Warn,
+}
+
+pub fn manifest_path(cfg: &Config) -> String {
+    format!("{}.sig", cfg.baseline_db)
+}
+
+/// Reads an Ed25519 secret key: either 32 raw bytes or 64 hex characters.
+pub fn load_signing_key(path: &str) -> Result<SigningKey> {
+    let seed = read_key_bytes(path)?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Reads an Ed25519 public key in the same formats as the secret key.
+pub fn load_verifying_key(path: &str) -> Result<VerifyingKey> {
+    let raw = read_key_bytes(path)?;
+    VerifyingKey::from_bytes(&raw).with_context(|| format!("invalid public key in {}", path))
+}
+
+fn read_key_bytes(path: &str) -> Result<[u8; 32]> {
+    let raw = fs::read(path).with_context(|| format!("failed to read key {}", path))?;
+    if raw.len() == 32 {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&raw);
+        return Ok(out);
+    }
+    let text = String::from_utf8_lossy(&raw);
+    let bytes = hex::decode(text.trim()).with_context(|| format!("key {} is neither 32 raw bytes nor hex", path))?;
+    bytes.try_into().map_err(|_| anyhow!("key {} must be 32 bytes", path))
+}
+
+/// BLAKE3 over every row of `files`, ordered by path. Returns the hex digest
+/// and the number of rows covered.
+pub fn table_digest(conn: &Connection) -> Result<(String, u64)> {
+    rows_digest(conn, &format!("SELECT path, {FILE_COLUMNS} FROM files ORDER BY path"), [])
+}
+
+/// The same digest over the `baseline_files` rows of generation `id`; equal to
+/// `table_digest` right after the generation is snapshotted or restored.
+pub fn generation_digest(conn: &Connection, id: i64) -> Result<(String, u64)> {
+    rows_digest(conn, &format!("SELECT path, {FILE_COLUMNS} FROM baseline_files WHERE generation=?1 ORDER BY path"), [id])
+}
+
+fn rows_digest(conn: &Connection, sql: &str, params: impl Params) -> Result<(String, u64)> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut count = 0u64;
+    while let Some(r) = rows.next()? {
+        for i in 0..=FILE_COLUMNS.split(',').count() {
+            match r.get_ref(i)? {
+                ValueRef::Null => { hasher.update(&[0]); }
+                ValueRef::Integer(v) => { hasher.update(&v.to_le_bytes()); }
+                ValueRef::Text(t) | ValueRef::Blob(t) => { hasher.update(t); hasher.update(&[0]); }
+                ValueRef::Real(f) => { hasher.update(&f.to_le_bytes()); }
+            }
+        }
+        count += 1;
+    }
+    Ok((hasher.finalize().to_hex().to_string(), count))
+}
+
+/// Signs the current table contents and writes the manifest atomically.
+pub fn write_manifest(conn: &Connection, cfg: &Config, key: &SigningKey) -> Result<()> {
+    let (digest, files) = table_digest(conn)?;
+    let manifest = Manifest {
+        files,
+        signature: sign_digest(key, &digest),
+        digest,
+        public_key: hex::encode(key.verifying_key().to_bytes()),
+        signed_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+    };
+    let path = manifest_path(cfg);
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("write manifest {}", tmp))?;
+    fs::rename(&tmp, &path).with_context(|| format!("rename manifest to {}", path))?;
+    Ok(())
+}
+
+/// Checks the manifest against the database and the configured public key.
+pub fn verify_manifest(conn: &Connection, cfg: &Config, key: &VerifyingKey) -> Result<()> {
+    let path = manifest_path(cfg);
+    let raw = fs::read_to_string(&path).with_context(|| format!("baseline manifest {} missing", path))?;
+    let manifest: Manifest = serde_json::from_str(&raw).with_context(|| format!("invalid manifest {}", path))?;
+
+    verify_digest(key, &manifest.digest, &manifest.signature)
+        .with_context(|| format!("manifest {}", path))?;
+
+    let (digest, files) = table_digest(conn)?;
+    if digest != manifest.digest {
+        bail!("baseline contents do not match the signed manifest ({} rows in db, {} signed)", files, manifest.files);
+    }
+    Ok(())
+}
+
+/// Hex Ed25519 signature over a hex digest.
+pub fn sign_digest(key: &SigningKey, digest: &str) -> String {
+    hex::encode(key.sign(digest.as_bytes()).to_bytes())
+}
+
+pub fn verify_digest(key: &VerifyingKey, digest: &str, signature: &str) -> Result<()> {
+    let sig_bytes: [u8; 64] = hex::decode(signature).ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("malformed signature"))?;
+    key.verify(digest.as_bytes(), &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| anyhow!("signature does not verify with the configured key"))
+}
+
+/// Key material resolved from the config.
+pub struct Keys {
+    pub signing: Option<SigningKey>,
+    pub verifying: Option<VerifyingKey>,
+}
+
+impl Keys {
+    pub fn from_config(cfg: &Config) -> Result<Self> {
+        let signing = cfg.signing_key.as_deref().map(load_signing_key).transpose()?;
+        let verifying = match cfg.verify_key.as_deref() {
+            Some(p) => Some(load_verifying_key(p)?),
+            None => signing.as_ref().map(|k| k.verifying_key()),
+        };
+        Ok(Self { signing, verifying })
+    }
+}
+
+/// Verifies the baseline before `scan` or `watch` use it. With
+/// `signature_policy = "warn"` a mismatch is logged instead of returned.
+pub fn check_baseline(conn: &Connection, cfg: &Config, keys: &Keys) -> Result<()> {
+    let key = match &keys.verifying {
+        Some(k) => k,
+        None => return Ok(()),
+    };
+    match verify_manifest(conn, cfg, key) {
+        Ok(()) => {
+            info!("baseline signature verified ({})", manifest_path(cfg));
+            Ok(())
+        }
+        Err(e) if cfg.signature_policy == SignaturePolicy::Warn => {
+            error!("!!! BASELINE SIGNATURE CHECK FAILED: {e:#} — continuing because signature_policy = \"warn\"");
+            Ok(())
+        }
+        Err(e) => Err(e.context("refusing to use unverified baseline")),
+    }
+}
+
+/// Re-signs after the baseline was changed in place (init, watch).
+pub fn resign(conn: &Connection, cfg: &Config, keys: &Keys) -> Result<()> {
+    match &keys.signing {
+        Some(k) => write_manifest(conn, cfg, k),
+        None => {
+            if keys.verifying.is_some() {
+                warn!("baseline changed but no signing_key is configured; manifest is now stale");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::auditlog::AuditLog;
+use crate::event::AuditEvent;
+use anyhow::Result;
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// Destination for audit events produced by `watch` and `scan`.
+pub trait EventSink: Send {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()>;
+
+    /// Called on SIGHUP; file-backed sinks open their path again.
+    fn reopen(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// JSONL file; fills in `prev` so the file stays a hash chain.
+impl EventSink for AuditLog {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        self.refresh()?;
+        let line = serde_json::to_string(&AuditEvent { prev: self.head().to_string(), ..evt.clone() })?;
+        self.append_line(&line)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        AuditLog::reopen(self)
+    }
+}
+
+/// One JSON object per line on stdout.
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer(&mut out, evt)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+/// Plain buffer, e.g. to hold events until a transaction commits.
+impl EventSink for Vec<AuditEvent> {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        self.push(evt.clone());
+        Ok(())
+    }
+}
+
+/// Collects events in memory; clones share the same buffer.
+#[derive(Debug, Default, Clone)]
+pub struct MemorySink {
+    events: Arc<Mutex<Vec<AuditEvent>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    pub fn take(&self) -> Vec<AuditEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl EventSink for MemorySink {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        self.events.lock().unwrap().push(evt.clone());
+        Ok(())
+    }
+}
+
+/// Sends every event to all inner sinks. A failing sink does not stop
+/// the others; the first error is returned.
+#[derive(Default)]
+pub struct FanOut {
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl FanOut {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, sink: impl EventSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    pub fn with(mut self, sink: impl EventSink + 'static) -> Self {
+        self.push(sink);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+}
+
+impl EventSink for FanOut {
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        let mut first = None;
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.emit(evt) {
+                first.get_or_insert(e);
+            }
+        }
+        first.map_or(Ok(()), Err)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        let mut first = None;
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.reopen() {
+                first.get_or_insert(e);
+            }
+        }
+        first.map_or(Ok(()), Err)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config::Config;
+use crate::event::AuditEvent;
+use crate::sink::EventSink;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+use time::OffsetDateTime;
+use tracing::warn;
+
+/// The watch loop sends synchronously: a dead collector may cost it this much.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Reconnect delay after a failed TCP connect, doubled up to `MAX_BACKOFF`.
+const BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// `[syslog]` section of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogConfig {
+    /// "udp" (default), "tcp" or "unix" (datagram socket, e.g. /dev/log for journald)
+    #[serde(default = "default_transport")]
+    pub transport: String,
+    /// host:port for udp/tcp, socket path for unix
+    #[serde(default = "default_address")]
+    pub address: String,
+    #[serde(default = "default_facility")]
+    pub facility: String,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    /// SD-ID of the structured data element
+    #[serde(default = "default_sd_id")]
+    pub sd_id: String,
+    /// Per-kind overrides of the default severity, e.g. `delete = "crit"`
+    #[serde(default)]
+    pub severity: BTreeMap<String, Level>,
+}
+
+/// RFC 5424 severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Emerg,
+    Alert,
+    Crit,
+    Err,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// Default severity by event kind.
+    pub fn for_kind(kind: &str) -> Self {
+        match kind {
+            "log_truncated" | "log_rewritten" | "mtime_reset" => Level::Err,
+            "delete" | "missing" | "missed_event" | "perm_change" | "owner_change" => Level::Warning,
+            "create" | "added" | "modify" | "changed" | "rename" | "times_change" => Level::Notice,
+            _ => Level::Info,
+        }
+    }
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    /// Reconnected lazily after a write error.
+    Tcp(Option<TcpStream>, Reconnect),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixDatagram),
+}
+
+/// No connect attempts before `retry_at`; events meanwhile are dropped.
+#[derive(Default)]
+struct Reconnect {
+    retry_at: Option<Instant>,
+    backoff: Duration,
+}
+
+pub struct Syslog {
+    cfg: SyslogConfig,
+    facility: u8,
+    transport: Transport,
+    /// `address` resolved once; forgotten when a connect fails
+    resolved: Option<SocketAddr>,
+}
+
+impl Syslog {
+    pub fn from_config(cfg: &Config) -> Result<Option<Self>> {
+        cfg.syslog.as_ref().map(|s| Self::open(s.clone())).transpose()
+    }
+
+    pub fn open(cfg: SyslogConfig) -> Result<Self> {
+        let facility = facility_code(&cfg.facility)?;
+        let transport = match cfg.transport.as_str() {
+            "udp" => Transport::Udp(UdpSocket::bind("0.0.0.0:0").context("bind syslog udp socket")?),
+            "tcp" => Transport::Tcp(None, Reconnect::default()),
+            #[cfg(unix)]
+            "unix" => Transport::Unix(std::os::unix::net::UnixDatagram::unbound()?),
+            other => bail!("unknown syslog transport: {other}"),
+        };
+        Ok(Self { cfg, facility, transport, resolved: None })
+    }
+
+    fn addr(&mut self) -> Result<SocketAddr> {
+        if let Some(a) = self.resolved {
+            return Ok(a);
+        }
+        let a = self.cfg.address.to_socket_addrs()
+            .with_context(|| format!("resolve syslog {}", self.cfg.address))?
+            .next()
+            .with_context(|| format!("no address for syslog {}", self.cfg.address))?;
+        self.resolved = Some(a);
+        Ok(a)
+    }
+
+    fn send(&mut self, evt: &Value) -> Result<()> {
+        let msg = self.format(evt);
+        let addr = match self.transport {
+            #[cfg(unix)]
+            Transport::Unix(_) => None,
+            _ => Some(self.addr()?),
+        };
+        match &mut self.transport {
+            Transport::Udp(sock) => {
+                sock.send_to(msg.as_bytes(), addr.unwrap())?;
+            }
+            Transport::Tcp(conn, re) => {
+                if conn.is_none() {
+                    if re.retry_at.is_some_and(|t| Instant::now() < t) {
+                        bail!("collector {} down, event dropped", self.cfg.address);
+                    }
+                    let s = match TcpStream::connect_timeout(&addr.unwrap(), CONNECT_TIMEOUT) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            re.backoff = if re.backoff.is_zero() { BACKOFF } else { (re.backoff * 2).min(MAX_BACKOFF) };
+                            re.retry_at = Some(Instant::now() + re.backoff);
+                            // the collector may have moved
+                            self.resolved = None;
+                            return Err(anyhow::Error::new(e)
+                                .context(format!("connect syslog {}, retry in {:?}", self.cfg.address, re.backoff)));
+                        }
+                    };
+                    s.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                    *re = Reconnect::default();
+                    *conn = Some(s);
+                }
+                // RFC 6587 octet counting
+                let framed = format!("{} {}", msg.len(), msg);
+                if let Err(e) = conn.as_mut().unwrap().write_all(framed.as_bytes()) {
+                    *conn = None;
+                    return Err(e.into());
+                }
+            }
+            #[cfg(unix)]
+            Transport::Unix(sock) => {
+                sock.send_to(msg.as_bytes(), &self.cfg.address)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] MSG`
+    pub fn format(&self, evt: &Value) -> String {
+        let kind = evt["kind"].as_str().unwrap_or("-");
+        let level = self.cfg.severity.get(kind).copied().unwrap_or_else(|| Level::for_kind(kind));
+        let pri = self.facility as u32 * 8 + level as u32;
+        let ts = evt["ts"].as_i64().map(timestamp).unwrap_or_else(|| "-".to_string());
+
+        let mut sd = format!("[{}", self.cfg.sd_id);
+        if let Some(fields) = evt.as_object() {
+            for (k, v) in fields {
+                let v = match v {
+                    Value::String(s) => s.clone(),
+                    Value::Null => continue,
+                    v => v.to_string(),
+                };
+                sd.push_str(&format!(" {}=\"{}\"", k, sd_escape(&v)));
+            }
+        }
+        sd.push(']');
+
+        format!(
+            "<{pri}>1 {ts} {} {} {} {} {sd} {} {}",
+            header_field(evt["host"].as_str().unwrap_or(""), 255),
+            header_field(&self.cfg.app_name, 48),
+            std::process::id(),
+            header_field(kind, 32),
+            kind,
+            evt["path"].as_str().unwrap_or(""),
+        )
+    }
+}
+
+impl EventSink for Syslog {
+    /// Delivery errors are logged, not returned: a down collector must not stop the audit log.
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        if let Err(e) = self.send(&serde_json::to_value(evt)?) {
+            warn!("syslog: {e}");
+        }
+        Ok(())
+    }
+}
+
+fn facility_code(name: &str) -> Result<u8> {
+    const NAMES: [&str; 12] = ["kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp"];
+    if let Some(i) = NAMES.iter().position(|n| *n == name) {
+        return Ok(i as u8);
+    }
+    match name.strip_prefix("local").and_then(|n| n.parse::<u8>().ok()) {
+        Some(n) if n <= 7 => Ok(16 + n),
+        _ => bail!("unknown syslog facility: {name}"),
+    }
+}
+
+/// Header fields are PRINTUSASCII without spaces, "-" when empty.
+fn header_field(s: &str, max: usize) -> String {
+    let v: String = s.chars().filter(|c| c.is_ascii_graphic()).take(max).collect();
+    if v.is_empty() { "-".to_string() } else { v }
+}
+
+fn sd_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]")
+}
+
+fn timestamp(ms: i64) -> String {
+    match OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000) {
+        Ok(t) => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            t.year(), t.month() as u8, t.day(), t.hour(), t.minute(), t.second(), t.millisecond()
+        ),
+        Err(_) => "-".to_string(),
+    }
+}
+
+fn default_transport() -> String { "udp".to_string() }
+fn default_address() -> String { "127.0.0.1:514".to_string() }
+fn default_facility() -> String { "authpriv".to_string() }
+fn default_app_name() -> String { "sentra_fim".to_string() }
+fn default_sd_id() -> String { "fim@32473".to_string() }
@@ -0,0 +1,282 @@
+use crate::config::Config;
+use crate::event::AuditEvent;
+use crate::sink::EventSink;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// `[webhook]` section of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Per-event JSON template with `{{field}}` placeholders; the raw event if unset
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Request body around a batch; `{{events}}` is replaced by the JSON array
+    #[serde(default)]
+    pub envelope: Option<String>,
+    /// Only send events with `alert: true` (rules with `action = "alert"`)
+    #[serde(default = "default_true")]
+    pub alerts_only: bool,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Longest a partial batch waits before it is sent
+    #[serde(default = "default_flush_ms")]
+    pub flush_ms: u64,
+    /// First retry delay, doubled on every failure up to `max_backoff_ms`
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Undelivered events, one per line; defaults to `<baseline_db>.webhook-queue`
+    #[serde(default)]
+    pub queue_path: Option<String>,
+    /// Oldest queued events are dropped beyond this
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+}
+
+/// Handle to the background delivery thread. Dropping it makes one last
+/// delivery attempt; whatever is still undelivered stays in the queue file
+/// and is picked up by the next run.
+pub struct Webhook {
+    tx: Option<mpsc::Sender<String>>,
+    worker: Option<thread::JoinHandle<()>>,
+    template: Option<String>,
+    alerts_only: bool,
+}
+
+impl Webhook {
+    pub fn from_config(cfg: &Config) -> Result<Option<Self>> {
+        cfg.webhook.as_ref().map(|w| Self::start(w, &queue_path(cfg, w))).transpose()
+    }
+
+    pub fn start(cfg: &WebhookConfig, queue: &str) -> Result<Self> {
+        let mut queue = Queue::load(PathBuf::from(queue))?;
+        if !queue.items.is_empty() {
+            info!("webhook: {} queued events from a previous run", queue.items.len());
+        }
+        let (tx, rx) = mpsc::channel();
+        let cfg_w = cfg.clone();
+        let worker = thread::Builder::new()
+            .name("webhook".into())
+            .spawn(move || deliver_loop(&cfg_w, &mut queue, rx))
+            .context("spawn webhook thread")?;
+        Ok(Self {
+            tx: Some(tx),
+            worker: Some(worker),
+            template: cfg.template.clone(),
+            alerts_only: cfg.alerts_only,
+        })
+    }
+
+    fn enqueue(&self, evt: &Value) -> Result<()> {
+        if self.alerts_only && evt.get("alert") == Some(&Value::Bool(false)) {
+            return Ok(());
+        }
+        let body = match &self.template {
+            Some(t) => {
+                let s = render(t, evt);
+                // keep compact, one event per queue line
+                serde_json::from_str::<Value>(&s)
+                    .with_context(|| format!("webhook template is not valid JSON: {s}"))?
+                    .to_string()
+            }
+            None => evt.to_string(),
+        };
+        if let Some(tx) = &self.tx {
+            tx.send(body).context("webhook thread stopped")?;
+        }
+        Ok(())
+    }
+}
+
+impl EventSink for Webhook {
+    /// Queueing problems are logged, not returned: a broken webhook must not stop the audit log.
+    fn emit(&mut self, evt: &AuditEvent) -> Result<()> {
+        if let Err(e) = self.enqueue(&serde_json::to_value(evt)?) {
+            warn!("webhook: {e}");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Webhook {
+    fn drop(&mut self) {
+        self.tx.take();
+        if let Some(w) = self.worker.take() {
+            let _ = w.join();
+        }
+    }
+}
+
+fn queue_path(cfg: &Config, w: &WebhookConfig) -> String {
+    w.queue_path.clone().unwrap_or_else(|| format!("{}.webhook-queue", cfg.baseline_db))
+}
+
+/// Substitutes `{{field}}` with top-level event fields. Strings are inserted
+/// JSON-escaped without quotes, so placeholders belong inside string literals;
+/// `{{event}}` inserts the whole event object.
+pub fn render(template: &str, evt: &Value) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + len].trim();
+        match (name, evt.get(name)) {
+            ("event", _) => out.push_str(&evt.to_string()),
+            (_, Some(Value::String(s))) => {
+                let quoted = Value::String(s.clone()).to_string();
+                out.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            (_, Some(Value::Null)) | (_, None) => {}
+            (_, Some(v)) => out.push_str(&v.to_string()),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Pending events mirrored to a file, so they survive restarts.
+struct Queue {
+    path: PathBuf,
+    items: VecDeque<String>,
+}
+
+impl Queue {
+    fn load(path: PathBuf) -> Result<Self> {
+        let items = match fs::read_to_string(&path) {
+            Ok(s) => s.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(e).with_context(|| format!("read webhook queue {}", path.display())),
+        };
+        Ok(Self { path, items })
+    }
+
+    fn push(&mut self, item: String, max: usize) -> Result<()> {
+        self.items.push_back(item);
+        if self.items.len() > max {
+            let extra = self.items.len() - max;
+            warn!("webhook queue full, dropping {extra} oldest events");
+            self.items.drain(..extra);
+            return self.rewrite();
+        }
+        let mut f = fs::OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("open webhook queue {}", self.path.display()))?;
+        writeln!(f, "{}", self.items.back().unwrap())?;
+        Ok(())
+    }
+
+    fn pop(&mut self, n: usize) -> Result<()> {
+        self.items.drain(..n);
+        self.rewrite()
+    }
+
+    fn rewrite(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        let mut body = String::new();
+        for item in &self.items {
+            body.push_str(item);
+            body.push('\n');
+        }
+        fs::write(&tmp, body)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn deliver_loop(cfg: &WebhookConfig, queue: &mut Queue, rx: mpsc::Receiver<String>) {
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_millis(cfg.timeout_ms)).build();
+    let flush = Duration::from_millis(cfg.flush_ms);
+    let batch = cfg.batch_size.max(1);
+    // a queue left over from the last run is due immediately
+    let mut oldest = (!queue.items.is_empty()).then(|| Instant::now() - flush);
+    let mut retry_at = Instant::now();
+    let mut backoff = Duration::from_millis(cfg.backoff_ms);
+
+    loop {
+        let now = Instant::now();
+        let wait = match oldest {
+            None => Duration::from_secs(3600),
+            Some(_) if queue.items.len() >= batch => retry_at.saturating_duration_since(now),
+            Some(t) => (t + flush).max(retry_at).saturating_duration_since(now),
+        };
+        match rx.recv_timeout(wait) {
+            Ok(item) => {
+                if let Err(e) = queue.push(item, cfg.max_queue) {
+                    warn!("webhook queue error: {e}");
+                }
+                oldest.get_or_insert_with(Instant::now);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let Some(t) = oldest else { continue };
+        let due = queue.items.len() >= batch || now >= t + flush;
+        if !due || now < retry_at {
+            continue;
+        }
+        match post(&agent, cfg, queue, batch) {
+            Ok(()) => {
+                backoff = Duration::from_millis(cfg.backoff_ms);
+                retry_at = now;
+                oldest = (!queue.items.is_empty()).then_some(t);
+            }
+            Err(e) => {
+                warn!("webhook delivery failed, retry in {:?}: {e}", backoff);
+                retry_at = now + backoff;
+                backoff = (backoff * 2).min(Duration::from_millis(cfg.max_backoff_ms));
+            }
+        }
+    }
+
+    // shutting down: one attempt per batch, the rest stays queued
+    while !queue.items.is_empty() {
+        if let Err(e) = post(&agent, cfg, queue, batch) {
+            warn!("webhook: {} events left in {}: {e}", queue.items.len(), queue.path.display());
+            break;
+        }
+    }
+}
+
+fn post(agent: &ureq::Agent, cfg: &WebhookConfig, queue: &mut Queue, batch: usize) -> Result<()> {
+    let n = queue.items.len().min(batch);
+    let events = format!("[{}]", queue.items.iter().take(n).cloned().collect::<Vec<_>>().join(","));
+    let body = match &cfg.envelope {
+        Some(env) => env.replace("{{events}}", &events),
+        None => events,
+    };
+    let mut req = agent.post(&cfg.url).set("Content-Type", "application/json");
+    for (k, v) in &cfg.headers {
+        req = req.set(k, v);
+    }
+    req.send_string(&body)?;
+    queue.pop(n)
+}
+
+fn default_true() -> bool { true }
+fn default_batch_size() -> usize { 100 }
+fn default_flush_ms() -> u64 { 2000 }
+fn default_backoff_ms() -> u64 { 1000 }
+fn default_max_backoff_ms() -> u64 { 300_000 }
+fn default_timeout_ms() -> u64 { 10_000 }
+fn default_max_queue() -> usize { 100_000 }
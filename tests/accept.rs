@@ -0,0 +1,125 @@
+use std::fs;
+use tempfile::tempdir;
+use sentra_fim::{accept, config::Config, fim};
+
+#[test]
+fn accept_updates_only_selected_rows() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("a.conf"), "a1").unwrap();
+    fs::write(data.join("b.conf"), "b1").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    fs::write(data.join("a.conf"), "a2").unwrap();
+    fs::write(data.join("b.conf"), "b2").unwrap();
+
+    let out = accept::accept(&cfg, &accept::AcceptRequest {
+        targets: vec!["**/a.conf".to_string()],
+        approver: "alice".to_string(),
+        ticket: Some("CHG-42".to_string()),
+        ..Default::default()
+    }).unwrap();
+    assert_eq!(out.updated.len(), 1);
+
+    let diff = dir.path().join("diff.jsonl").to_string_lossy().to_string();
+    fim::scan_diff(&cfg, Some(diff.clone())).unwrap();
+    let content = fs::read_to_string(&diff).unwrap();
+    assert!(content.contains("b.conf") && !content.contains("a.conf"));
+
+    // b.conf moves on after the diff was reviewed: must not be accepted
+    fs::write(data.join("b.conf"), "b3").unwrap();
+    let out = accept::accept(&cfg, &accept::AcceptRequest {
+        diff: Some(diff),
+        approver: "alice".to_string(),
+        ..Default::default()
+    }).unwrap();
+    assert!(out.updated.is_empty());
+    assert_eq!(out.skipped[0].1, "content changed since diff");
+
+    let conn = rusqlite::Connection::open(&cfg.baseline_db).unwrap();
+    let (n, ticket): (i64, String) = conn
+        .query_row("SELECT COUNT(*), MAX(ticket) FROM approvals WHERE approver='alice'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(ticket, "CHG-42");
+}
+
+#[test]
+fn accept_skips_paths_no_scan_covers() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("a.conf"), "a1").unwrap();
+    fs::write(data.join("a.swp"), "tmp").unwrap();
+    fs::write(dir.path().join("elsewhere.conf"), "x").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        exclude: vec!["**/*.swp".to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    let out = accept::accept(&cfg, &accept::AcceptRequest {
+        targets: vec![
+            dir.path().join("elsewhere.conf").to_string_lossy().to_string(),
+            data.join("a.swp").to_string_lossy().to_string(),
+        ],
+        approver: "alice".to_string(),
+        ..Default::default()
+    }).unwrap();
+    assert!(out.updated.is_empty());
+    let mut reasons: Vec<&str> = out.skipped.iter().map(|(_, r)| r.as_str()).collect();
+    reasons.sort();
+    assert_eq!(reasons, ["excluded", "outside watch_paths"]);
+}
+
+#[test]
+fn accept_pins_content_of_metadata_only_diff_entries() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("a.conf"), "a1").unwrap();
+    fs::write(data.join("b.conf"), "b1").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    fs::set_permissions(data.join("a.conf"), fs::Permissions::from_mode(0o600)).unwrap();
+    fs::remove_file(data.join("b.conf")).unwrap();
+    let diff = dir.path().join("diff.jsonl").to_string_lossy().to_string();
+    fim::scan_diff(&cfg, Some(diff.clone())).unwrap();
+    let content = fs::read_to_string(&diff).unwrap();
+    assert!(content.contains("perm_change") && content.contains("missing"), "{content}");
+
+    // the reviewer saw a mode change and a deletion; the disk moved on since
+    fs::write(data.join("a.conf"), "evil").unwrap();
+    fs::write(data.join("b.conf"), "back").unwrap();
+    let out = accept::accept(&cfg, &accept::AcceptRequest {
+        diff: Some(diff),
+        approver: "alice".to_string(),
+        ..Default::default()
+    }).unwrap();
+    assert!(out.updated.is_empty() && out.removed.is_empty(), "{out:?}");
+    let mut reasons: Vec<&str> = out.skipped.iter().map(|(_, r)| r.as_str()).collect();
+    reasons.sort();
+    assert_eq!(reasons, ["content changed since diff", "file reappeared since diff"]);
+}
@@ -0,0 +1,122 @@
+
+use std::{fs, io::{BufRead, BufReader}};
+use tempfile::tempdir;
+use serde_json::Value;
+use sentra_fim::{
+    api::{self, EventStream},
+    auditlog::AuditLog,
+    config::Config,
+    event::{AuditEvent, EventKind},
+    fim,
+    http,
+    metrics::{self, Metrics},
+    sink::EventSink,
+};
+
+fn config(dir: &std::path::Path, root: &std::path::Path) -> Config {
+    Config {
+        baseline_db: dir.join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![root.to_string_lossy().to_string()],
+        debounce_ms: 0,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Starts the watch HTTP server on a free port; keep the runtime alive.
+fn serve(cfg: &Config, jsonl: String, prom: Metrics, live: EventStream) -> (tokio::runtime::Runtime, String) {
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let app = metrics::metrics_router(prom.registry()).merge(api::router(cfg.clone(), jsonl, prom, live));
+    rt.block_on(http::serve(&format!("127.0.0.1:{port}"), app, None)).unwrap();
+    (rt, format!("http://127.0.0.1:{port}"))
+}
+
+fn get(url: &str) -> (u16, Value) {
+    let resp = match ureq::get(url).call() {
+        Ok(r) => r,
+        Err(ureq::Error::Status(_, r)) => r,
+        Err(e) => panic!("{url}: {e}"),
+    };
+    let code = resp.status();
+    (code, serde_json::from_str(&resp.into_string().unwrap()).unwrap())
+}
+
+#[test]
+fn api_serves_baseline_events_and_status() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "hello").unwrap();
+    let cfg = config(dir.path(), &root);
+    fim::build_baseline(&cfg).unwrap();
+
+    let jsonl = dir.path().join("events.jsonl").to_string_lossy().to_string();
+    let mut log = AuditLog::open_append(&jsonl).unwrap();
+    let mut old = AuditEvent::new(EventKind::Modify, "/old".to_string());
+    old.ts -= 2 * 3_600_000;
+    log.emit(&old).unwrap();
+    log.emit(&AuditEvent::new(EventKind::Create, "/new-1".to_string())).unwrap();
+    log.emit(&AuditEvent::new(EventKind::Delete, "/new-2".to_string())).unwrap();
+
+    let prom = Metrics::try_new().unwrap();
+    prom.tracked_files.set(1);
+    let (_rt, base) = serve(&cfg, jsonl, prom, EventStream::new());
+
+    let a = root.join("a.txt").to_string_lossy().to_string();
+    let (code, file) = get(&format!("{base}/files?path={a}"));
+    assert_eq!(code, 200);
+    assert_eq!(file["hash"], blake3::hash(b"hello").to_hex().as_str());
+    assert_eq!(file["size"], 5);
+    let (code, _) = get(&format!("{base}/files?path=/nope"));
+    assert_eq!(code, 404);
+
+    let (_, events) = get(&format!("{base}/events"));
+    let paths: Vec<&str> = events.as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, ["/new-1", "/new-2"], "default window is the last hour");
+    let (_, events) = get(&format!("{base}/events?since=1d&limit=1"));
+    assert_eq!(events[0]["path"], "/new-2");
+    let (code, _) = get(&format!("{base}/events?since=yesterday"));
+    assert_eq!(code, 400);
+
+    let (_, status) = get(&format!("{base}/status"));
+    assert_eq!(status["roots"][0], root.to_string_lossy().as_ref());
+    assert_eq!(status["tracked_files"], 1);
+    assert_eq!(status["watcher"]["up"], false);
+}
+
+#[test]
+fn event_stream_pushes_filtered_events() {
+    let dir = tempdir().unwrap();
+    let cfg = config(dir.path(), dir.path());
+    let live = EventStream::new();
+    let (_rt, base) = serve(&cfg, String::new(), Metrics::try_new().unwrap(), live.clone());
+
+    let resp = ureq::get(&format!("{base}/events/stream?path=/etc/**&kind=modify,delete")).call().unwrap();
+    assert_eq!(resp.header("content-type"), Some("text/event-stream"));
+    assert_eq!(live.clients(), 1);
+
+    let mut sink = live.clone();
+    for (kind, path) in [
+        (EventKind::Modify, "/etc/passwd"),
+        (EventKind::Modify, "/var/log/x"),
+        (EventKind::Create, "/etc/new"),
+        (EventKind::Delete, "/etc/shadow"),
+    ] {
+        sink.emit(&AuditEvent::new(kind, path.to_string())).unwrap();
+    }
+
+    let mut got = Vec::new();
+    for line in BufReader::new(resp.into_reader()).lines() {
+        let line = line.unwrap();
+        if let Some(data) = line.strip_prefix("data: ") {
+            let e: AuditEvent = serde_json::from_str(data).unwrap();
+            got.push(e.path);
+            if got.len() == 2 { break; }
+        }
+    }
+    assert_eq!(got, ["/etc/passwd", "/etc/shadow"]);
+
+    let (code, _) = get(&format!("{base}/events/stream?kind=bogus"));
+    assert_eq!(code, 400);
+}
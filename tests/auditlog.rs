@@ -0,0 +1,80 @@
+use std::fs;
+use tempfile::tempdir;
+use sentra_fim::{auditlog, config::Config, fim};
+
+#[test]
+fn chain_verifies_and_detects_tampering() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    for i in 0..3 {
+        fs::write(data.join(format!("f{i}")), format!("v{i}")).unwrap();
+    }
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    // empty baseline -> every file is reported as added
+    fim::build_baseline(&Config { watch_paths: vec![], ..cfg.clone() }).unwrap();
+
+    let jsonl = dir.path().join("diff.jsonl").to_string_lossy().to_string();
+    fim::scan_diff(&cfg, Some(jsonl.clone())).unwrap();
+
+    let report = auditlog::verify_log(&jsonl).unwrap();
+    assert_eq!(report.records, 3);
+    assert!(report.broken.is_none());
+
+    // drop the middle record
+    let content = fs::read_to_string(&jsonl).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    fs::write(&jsonl, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+
+    let report = auditlog::verify_log(&jsonl).unwrap();
+    assert_eq!(report.broken.unwrap().line, 2);
+}
+
+#[test]
+fn reopen_recovers_chain_head() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+
+    let mut log = auditlog::AuditLog::create(&path).unwrap();
+    log.append_line(&format!("{{\"prev\":\"{}\",\"kind\":\"create\"}}", log.head())).unwrap();
+    let head = log.head().to_string();
+    drop(log);
+
+    let mut log = auditlog::AuditLog::open_append(&path).unwrap();
+    assert_eq!(log.head(), head);
+    log.append_line(&format!("{{\"prev\":\"{}\",\"kind\":\"delete\"}}", log.head())).unwrap();
+
+    let report = auditlog::verify_log(&path).unwrap();
+    assert_eq!(report.records, 2);
+    assert!(report.broken.is_none());
+}
+
+#[test]
+fn dropping_trailing_records_is_reported() {
+    use sentra_fim::{event::{AuditEvent, EventKind}, sink::EventSink};
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+    let mut log = auditlog::AuditLog::create(&path).unwrap();
+    for p in ["/a", "/b", "/c"] {
+        log.emit(&AuditEvent::new(EventKind::Modify, p.to_string())).unwrap();
+    }
+    let (lost, size) = (log.head().to_string(), log.len());
+
+    // keep only the first record, as someone covering their tracks would
+    let first = fs::read_to_string(&path).unwrap().lines().next().unwrap().len() as u64 + 1;
+    fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(first).unwrap();
+    log.emit(&AuditEvent::new(EventKind::Modify, "/d".to_string())).unwrap();
+
+    let report = auditlog::verify_log(&path).unwrap();
+    assert_eq!(report.records, 3);
+    assert!(report.broken.is_none());
+    let cut = &report.truncated[0];
+    assert_eq!((cut.line, cut.lost_head.as_str(), cut.lost_size), (2, lost.as_str(), size));
+}
@@ -1,7 +1,7 @@
 
 use std::{fs, io::Write};
 use tempfile::tempdir;
-use sentra_fim::{config::Config, fim};
+use sentra_fim::{config::Config, fim, signing::SignaturePolicy};
 
 #[test]
 fn baseline_and_scan_jsonl() {
@@ -14,11 +14,10 @@ fn baseline_and_scan_jsonl() {
     // config
     let cfg = Config {
         baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
-        metrics_bind: "127.0.0.1:0".to_string(),
         watch_paths: vec![dir.path().to_string_lossy().to_string()],
-        exclude: vec![],
-        hash_alg: "blake3".to_string(),
         debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
     };
 
     // baseline
@@ -35,3 +34,148 @@ fn baseline_and_scan_jsonl() {
     let content = fs::read_to_string(jsonl).unwrap();
     assert!(content.contains("\"kind\":\"changed\"") || content.contains("\"kind\":\"added\""));
 }
+
+#[cfg(unix)]
+#[test]
+fn scan_reports_perm_change() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("tool");
+    fs::write(&p, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&p, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![dir.path().join("tool").to_string_lossy().to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    // setuid bit only, content untouched
+    fs::set_permissions(&p, fs::Permissions::from_mode(0o4755)).unwrap();
+
+    let jsonl = dir.path().join("diff.jsonl");
+    fim::scan_diff(&cfg, Some(jsonl.to_string_lossy().to_string())).unwrap();
+
+    let content = fs::read_to_string(jsonl).unwrap();
+    assert!(content.contains("\"kind\":\"perm_change\""));
+    assert!(content.contains("\"new_mode\":\"4755\""));
+    assert!(!content.contains("\"kind\":\"changed\""));
+}
+
+#[test]
+fn signed_baseline_rejects_tampering() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("a.txt"), "hello").unwrap();
+    let key = dir.path().join("baseline.key");
+    fs::write(&key, "11".repeat(32)).unwrap();
+
+    let mut cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        signing_key: Some(key.to_string_lossy().to_string()),
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+    fim::scan_diff(&cfg, None).unwrap();
+
+    // legitimise a change behind the monitor's back
+    let conn = rusqlite::Connection::open(&cfg.baseline_db).unwrap();
+    conn.execute("UPDATE files SET hash='00'", []).unwrap();
+    drop(conn);
+
+    let err = fim::scan_diff(&cfg, None).unwrap_err();
+    assert!(format!("{err:#}").contains("do not match the signed manifest"));
+
+    cfg.signature_policy = SignaturePolicy::Warn;
+    fim::scan_diff(&cfg, None).unwrap();
+
+    // a typo must not quietly mean "enforce" or "off"
+    let toml_path = dir.path().join("config.toml");
+    let base = "baseline_db = \"b.db\"\nmetrics_bind = \"127.0.0.1:0\"\nwatch_paths = []\n";
+    fs::write(&toml_path, format!("{base}signature_policy = \"warning\"\n")).unwrap();
+    let err = Config::load(toml_path.to_str().unwrap()).unwrap_err();
+    assert!(format!("{err:#}").contains("unknown variant `warning`"), "{err:#}");
+    fs::write(&toml_path, format!("{base}signature_policy = \"warn\"\n")).unwrap();
+    assert_eq!(Config::load(toml_path.to_str().unwrap()).unwrap().signature_policy, SignaturePolicy::Warn);
+}
+
+#[test]
+fn parallel_baseline_matches_single_thread() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    for d in 0..5 {
+        let sub = data.join(format!("d{d}"));
+        fs::create_dir_all(&sub).unwrap();
+        for i in 0..40 {
+            fs::write(sub.join(format!("f{i}")), format!("{d}-{i}")).unwrap();
+        }
+    }
+
+    let rows = |threads: usize, db: &str| {
+        let cfg = Config {
+            baseline_db: dir.path().join(db).to_string_lossy().to_string(),
+            watch_paths: vec![data.to_string_lossy().to_string()],
+            debounce_ms: 10,
+            hash_threads: threads,
+            reconcile: "off".to_string(),
+            ..Default::default()
+        };
+        fim::build_baseline(&cfg).unwrap();
+        let conn = rusqlite::Connection::open(&cfg.baseline_db).unwrap();
+        let mut stmt = conn.prepare("SELECT path, hash FROM files ORDER BY rowid").unwrap();
+        let v: Vec<(String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap().map(|r| r.unwrap()).collect();
+        v
+    };
+
+    let single = rows(1, "one.db");
+    let multi = rows(4, "four.db");
+    assert_eq!(single.len(), 200);
+    // same rows, inserted in the same (walk) order
+    assert_eq!(single, multi);
+}
+
+#[test]
+fn scan_modes_fast_path_and_mtime_reset() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    let p = data.join("sshd_config");
+    fs::write(&p, "PermitRootLogin no\n").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        hash_threads: 2,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    let s = fim::scan_with(&cfg, None, fim::ScanMode::Fast).unwrap();
+    assert_eq!((s.trusted, s.hashed, s.changed), (1, 0, 0));
+
+    // same size, mtime put back afterwards
+    let mtime = fs::metadata(&p).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(&p, "PermitRootLogin ye\n").unwrap();
+    fs::File::options().write(true).open(&p).unwrap().set_modified(mtime).unwrap();
+
+    let s = fim::scan_with(&cfg, None, fim::ScanMode::Fast).unwrap();
+    assert_eq!((s.trusted, s.hashed, s.changed), (0, 1, 1));
+
+    let s = fim::scan_with(&cfg, None, fim::ScanMode::Paranoid).unwrap();
+    if cfg!(unix) {
+        assert_eq!((s.rechecked, s.mtime_reset), (1, 1));
+    }
+    assert_eq!(s.changed, 1);
+}
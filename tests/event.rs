@@ -0,0 +1,39 @@
+
+use sentra_fim::{
+    event::{AuditEvent, EventKind, EventReader, SCHEMA_VERSION},
+    policy::Severity,
+};
+
+#[test]
+fn reader_parses_legacy_and_current_records() {
+    let mut current = AuditEvent::new(EventKind::PermChange, "/etc/shadow".to_string());
+    current.old_mode = Some("640".to_string());
+    current.new_mode = Some("666".to_string());
+    current.severity = Severity::High;
+
+    let log = format!(
+        "{}\n\n{}\n{}\n",
+        // written before events were versioned: no schema_version, host, severity or alert
+        r#"{"ts":1700000000000,"kind":"modify","path":"/etc/hosts","old_hash":"aa","new_hash":"bb","size":3}"#,
+        serde_json::to_string(&current).unwrap(),
+        r#"{"schema_version":2,"ts":1,"host":"h","kind":"quarantined","path":"/x","extra":true}"#,
+    );
+    let events: Vec<AuditEvent> = EventReader::new(log.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(events.len(), 3);
+
+    let legacy = &events[0];
+    assert_eq!(legacy.schema_version, 0);
+    assert_eq!(legacy.kind, EventKind::Modify);
+    assert_eq!(legacy.new_hash.as_deref(), Some("bb"));
+    assert_eq!(legacy.severity, Severity::Medium);
+    assert!(legacy.alert && legacy.host.is_empty());
+
+    assert_eq!(events[1], current);
+    assert_eq!(events[1].schema_version, SCHEMA_VERSION);
+    assert!(!events[1].host.is_empty());
+
+    assert_eq!(events[2].kind, EventKind::Unknown);
+
+    let bad = EventReader::new("{\"kind\":\"create\"}\n".as_bytes()).next().unwrap();
+    assert!(bad.unwrap_err().to_string().contains("line 1"));
+}
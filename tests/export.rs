@@ -0,0 +1,94 @@
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+};
+use tempfile::tempdir;
+use sentra_fim::{config::Config, export::ExportConfig, fim};
+
+fn config(dir: &std::path::Path, root: &std::path::Path, export: ExportConfig) -> Config {
+    Config {
+        baseline_db: dir.join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![root.to_string_lossy().to_string()],
+        debounce_ms: 0,
+        reconcile: "off".to_string(),
+        metrics_export: Some(export),
+        ..Default::default()
+    }
+}
+
+/// Pushgateway stand-in: sends back "<request line>\n<body>" of each request.
+fn pushgateway() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() { break; }
+                if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    len = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            tx.send(format!("{}\n{}", request.trim(), String::from_utf8(body).unwrap())).unwrap();
+        }
+    });
+    (url, rx)
+}
+
+#[test]
+fn init_and_scan_export_textfile_and_push() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    let textfiles = dir.path().join("textfile");
+    fs::create_dir_all(&root).unwrap();
+    fs::create_dir_all(&textfiles).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("b.txt"), "b").unwrap();
+    let (url, pushed) = pushgateway();
+    let cfg = config(dir.path(), &root, ExportConfig {
+        textfile_dir: Some(textfiles.to_string_lossy().to_string()),
+        pushgateway: Some(url),
+        job: "fim".to_string(),
+        instance: Some("web 1/a".to_string()),
+        timeout_ms: 2000,
+    });
+
+    fim::build_baseline(&cfg).unwrap();
+    let init = fs::read_to_string(textfiles.join("sentra_fim_init.prom")).unwrap();
+    assert!(init.contains(r#"fim_run_files_hashed{command="init"} 2"#), "{init}");
+    assert!(init.contains(r#"fim_tracked_files{command="init"} 2"#), "{init}");
+    assert!(!init.contains("fim_watcher_up"), "watch-only metrics stay out");
+
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::remove_file(root.join("b.txt")).unwrap();
+    fs::write(root.join("c.txt"), "c").unwrap();
+    fim::scan_diff(&cfg, Some(dir.path().join("diff.jsonl").to_string_lossy().to_string())).unwrap();
+    let scan = fs::read_to_string(textfiles.join("sentra_fim_scan.prom")).unwrap();
+    for kind in ["added", "changed", "missing"] {
+        assert!(scan.contains(&format!(r#"fim_scan_drift_files{{command="scan",kind="{kind}"}} 1"#)), "{scan}");
+    }
+    assert!(scan.contains(r#"fim_hash_duration_seconds_count{command="scan"} 2"#), "{scan}");
+    assert!(scan.contains(r#"fim_tracked_files{command="scan"} 2"#), "{scan}");
+    let names: Vec<_> = fs::read_dir(&textfiles).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(names.len(), 2, "no temp files left: {names:?}");
+
+    let first = pushed.recv().unwrap();
+    assert!(first.starts_with("PUT /metrics/job/fim/instance@base64/d2ViIDEvYQ/command/init "), "{first}");
+    let second = pushed.recv().unwrap();
+    assert!(second.contains("/command/scan "), "{second}");
+    assert!(second.contains(r#"fim_scan_drift_files{command="scan",kind="changed"} 1"#), "{second}");
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use tempfile::tempdir;
+use sentra_fim::{accept, config::Config, fim, history};
+
+#[test]
+fn generations_diff_and_rollback() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("app.conf"), "v1").unwrap();
+    fs::write(data.join("old.txt"), "bye").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    let g1 = fim::build_generation(&cfg, Some("first deploy")).unwrap();
+
+    fs::write(data.join("app.conf"), "v2").unwrap();
+    fs::remove_file(data.join("old.txt")).unwrap();
+    fs::write(data.join("new.txt"), "hi").unwrap();
+    let g2 = fim::build_generation(&cfg, None).unwrap();
+
+    let gens = history::list_generations(&cfg).unwrap();
+    assert_eq!(gens.len(), 2);
+    assert_eq!(gens[0].comment.as_deref(), Some("first deploy"));
+    assert!(!gens[0].active && gens[1].active);
+
+    let d = history::diff_generations(&cfg, g1, g2).unwrap();
+    assert_eq!(d.added.len(), 1);
+    assert_eq!(d.changed.len(), 1);
+    assert_eq!(d.removed.len(), 1);
+    assert!(d.changed[0].ends_with("app.conf"));
+
+    history::rollback(&cfg, g1).unwrap();
+    let gens = history::list_generations(&cfg).unwrap();
+    assert!(gens[0].active && !gens[1].active);
+    let d = history::diff_generations(&cfg, g1, g1).unwrap();
+    assert!(d.added.is_empty() && d.changed.is_empty() && d.removed.is_empty());
+
+    assert!(history::rollback(&cfg, 99).is_err());
+}
+
+#[test]
+fn rollback_refuses_tampered_generations() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("app.conf"), "v1").unwrap();
+    let key = dir.path().join("baseline.key");
+    fs::write(&key, "11".repeat(32)).unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        signing_key: Some(key.to_string_lossy().to_string()),
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    let g1 = fim::build_generation(&cfg, None).unwrap();
+    fs::write(data.join("app.conf"), "v2").unwrap();
+    let g2 = fim::build_generation(&cfg, None).unwrap();
+
+    // swap in the hash of an attacker's file and fix up the recorded digest
+    let conn = rusqlite::Connection::open(&cfg.baseline_db).unwrap();
+    conn.execute("UPDATE baseline_files SET hash='00' WHERE generation=?1", [g1]).unwrap();
+    let err = history::rollback(&cfg, g1).unwrap_err();
+    assert!(format!("{err:#}").contains("does not match the digest"), "{err:#}");
+
+    let (digest, _) = sentra_fim::signing::generation_digest(&conn, g1).unwrap();
+    conn.execute("UPDATE baselines SET digest=?1 WHERE id=?2", rusqlite::params![digest, g1]).unwrap();
+    let err = history::rollback(&cfg, g1).unwrap_err();
+    assert!(format!("{err:#}").contains("signature does not verify"), "{err:#}");
+
+    let gens = history::list_generations(&cfg).unwrap();
+    assert!(gens.iter().find(|g| g.id == g2).unwrap().active, "nothing was restored");
+    history::rollback(&cfg, g2).unwrap();
+}
+
+#[test]
+fn accept_creates_a_generation_that_rollback_respects() {
+    let dir = tempdir().unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("app.conf"), "v1").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![data.to_string_lossy().to_string()],
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    let g1 = fim::build_generation(&cfg, None).unwrap();
+    fs::write(data.join("app.conf"), "v2").unwrap();
+    let out = accept::accept(&cfg, &accept::AcceptRequest {
+        targets: vec![data.join("app.conf").to_string_lossy().to_string()],
+        approver: "alice".to_string(),
+        ticket: Some("CHG-7".to_string()),
+        ..Default::default()
+    }).unwrap();
+    let g2 = out.generation.unwrap();
+
+    let gens = history::list_generations(&cfg).unwrap();
+    assert_eq!(gens.len(), 2);
+    assert!(gens[1].active && gens[1].comment.as_deref() == Some("accepted by alice (CHG-7)"));
+    let d = history::diff_generations(&cfg, g1, g2).unwrap();
+    assert!(d.changed[0].ends_with("app.conf"));
+
+    // back to the pre-accept baseline: v2 is drift again
+    history::rollback(&cfg, g1).unwrap();
+    let diff = dir.path().join("diff.jsonl").to_string_lossy().to_string();
+    fim::scan_diff(&cfg, Some(diff.clone())).unwrap();
+    assert!(fs::read_to_string(&diff).unwrap().contains("\"changed\""));
+
+    // and forward again: the accepted content is clean
+    history::rollback(&cfg, g2).unwrap();
+    fim::scan_diff(&cfg, Some(diff.clone())).unwrap();
+    assert!(fs::read_to_string(&diff).unwrap().is_empty());
+}
@@ -0,0 +1,121 @@
+
+use std::{fs, sync::Arc};
+use tempfile::tempdir;
+use base64::Engine;
+use tokio_rustls::rustls;
+use sentra_fim::{
+    api::{self, EventStream},
+    config::Config,
+    health,
+    http::{self, HttpConfig},
+    metrics::{self, Metrics},
+};
+
+fn config(dir: &std::path::Path) -> Config {
+    Config {
+        baseline_db: dir.join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![dir.to_string_lossy().to_string()],
+        debounce_ms: 0,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    }
+}
+
+fn free_addr() -> String {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string()
+}
+
+/// Starts both listeners as `watch` would; keep the runtime alive.
+fn start(cfg: &Config, metrics_bind: &str, http_cfg: &HttpConfig) -> tokio::runtime::Runtime {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let prom = Metrics::try_new().unwrap();
+    let api = api::router(cfg.clone(), String::new(), prom.clone(), EventStream::new());
+    let probes = metrics::metrics_router(prom.registry()).merge(health::router(cfg.clone(), prom.clone()));
+    rt.block_on(http::start(metrics_bind, probes, api, http_cfg)).unwrap();
+    rt
+}
+
+fn status(req: ureq::Request) -> u16 {
+    match req.call() {
+        Ok(r) => r.status(),
+        Err(ureq::Error::Status(code, _)) => code,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+#[test]
+fn auth_guards_admin_and_metrics_listeners() {
+    let dir = tempdir().unwrap();
+    let cfg = config(dir.path());
+    let (metrics_addr, admin_addr) = (free_addr(), free_addr());
+    let http_cfg = HttpConfig {
+        admin_bind: Some(admin_addr.clone()),
+        bearer_token: Some("s3cret".to_string()),
+        basic_user: Some("prom".to_string()),
+        basic_password: Some("scrape".to_string()),
+        auth_metrics: true,
+        ..Default::default()
+    };
+    let _rt = start(&cfg, &metrics_addr, &http_cfg);
+
+    let admin = format!("http://{admin_addr}/status");
+    assert_eq!(status(ureq::get(&admin)), 401);
+    assert_eq!(status(ureq::get(&admin).set("Authorization", "Bearer wrong")), 401);
+    assert_eq!(status(ureq::get(&admin).set("Authorization", "Bearer s3cret")), 200);
+
+    let basic = base64::engine::general_purpose::STANDARD.encode("prom:scrape");
+    let metrics = format!("http://{metrics_addr}/metrics");
+    assert_eq!(status(ureq::get(&metrics)), 401);
+    assert_eq!(status(ureq::get(&metrics).set("Authorization", &format!("Basic {basic}"))), 200);
+    // probes stay open; the API lives only on the admin listener
+    assert_eq!(status(ureq::get(&format!("http://{metrics_addr}/readyz"))), 503, "open, but not ready");
+    assert_eq!(status(ureq::get(&format!("http://{metrics_addr}/status")).set("Authorization", "Bearer s3cret")), 404);
+
+    let open = HttpConfig { admin_bind: Some(free_addr()), auth_metrics: false, ..http_cfg };
+    let metrics_addr = free_addr();
+    let _rt2 = start(&cfg, &metrics_addr, &open);
+    assert_eq!(status(ureq::get(&format!("http://{metrics_addr}/metrics"))), 200);
+
+    // one listener for both: the flag still opens /metrics, never the API
+    let shared = HttpConfig { admin_bind: None, ..open };
+    let addr = free_addr();
+    let _rt3 = start(&cfg, &addr, &shared);
+    assert_eq!(status(ureq::get(&format!("http://{addr}/metrics"))), 200);
+    assert_eq!(status(ureq::get(&format!("http://{addr}/status"))), 401);
+    assert_eq!(status(ureq::get(&format!("http://{addr}/status")).set("Authorization", "Bearer s3cret")), 200);
+}
+
+#[test]
+fn tls_listener_serves_https_only() {
+    let dir = tempdir().unwrap();
+    let cfg = config(dir.path());
+    let ck = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let (cert, key) = (dir.path().join("tls.crt"), dir.path().join("tls.key"));
+    fs::write(&cert, ck.cert.pem()).unwrap();
+    fs::write(&key, ck.key_pair.serialize_pem()).unwrap();
+
+    let addr = free_addr();
+    let http_cfg = HttpConfig {
+        tls_cert: Some(cert.to_string_lossy().to_string()),
+        tls_key: Some(key.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    let _rt = start(&cfg, &addr, &http_cfg);
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(ck.cert.der().clone()).unwrap();
+    let tls = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions().unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let agent = ureq::AgentBuilder::new().tls_config(Arc::new(tls)).build();
+    let port = addr.rsplit(':').next().unwrap();
+    assert_eq!(status(agent.get(&format!("https://localhost:{port}/metrics"))), 200);
+    assert_eq!(status(agent.get(&format!("https://localhost:{port}/status"))), 200);
+    assert!(ureq::get(&format!("http://{addr}/metrics")).call().is_err());
+
+    let half = HttpConfig { tls_key: None, ..http_cfg };
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let err = rt.block_on(http::start(&free_addr(), axum::Router::new(), axum::Router::new(), &half)).unwrap_err();
+    assert!(err.to_string().contains("tls_cert and tls_key"), "{err}");
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use tempfile::tempdir;
+use sentra_fim::{config::Config, fim};
+
+#[test]
+fn rules_select_checks_and_severity() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    for d in ["bin", "etc", "logs"] {
+        fs::create_dir_all(root.join(d)).unwrap();
+    }
+    fs::write(root.join("bin/ls"), "elf").unwrap();
+    fs::write(root.join("etc/hosts"), "127.0.0.1 localhost").unwrap();
+    fs::write(root.join("logs/app.log"), "line1\n").unwrap();
+
+    let cfg_path = dir.path().join("config.toml");
+    fs::write(&cfg_path, format!(r#"
+baseline_db = "{db}"
+metrics_bind = "127.0.0.1:0"
+watch_paths = ["{root}"]
+
+[[rule]]
+name = "binaries"
+glob = "**/bin/**"
+severity = "critical"
+
+[[rule]]
+glob = "**/logs/**"
+action = "ignore"
+
+[[rule]]
+name = "etc-meta"
+glob = "**/etc/**"
+check = ["perm", "owner"]
+severity = "high"
+action = "log"
+"#, db = dir.path().join("base.db").display(), root = root.display())).unwrap();
+    let cfg = Config::load(cfg_path.to_str().unwrap()).unwrap();
+    fim::build_baseline(&cfg).unwrap();
+
+    fs::write(root.join("bin/ls"), "trojan").unwrap();
+    fs::write(root.join("etc/hosts"), "10.0.0.1 localhost").unwrap();
+    fs::remove_file(root.join("logs/app.log")).unwrap();
+
+    let jsonl = dir.path().join("diff.jsonl");
+    let sum = fim::scan_with(&cfg, Some(jsonl.to_string_lossy().to_string()), fim::ScanMode::Full).unwrap();
+    assert_eq!((sum.changed, sum.missing), (1, 0));
+
+    let content = fs::read_to_string(&jsonl).unwrap();
+    let line = content.lines().find(|l| l.contains("bin/ls")).unwrap();
+    assert!(line.contains("\"severity\":\"critical\""));
+    assert!(line.contains("\"rule\":\"binaries\""));
+    assert!(!content.contains("etc/hosts"));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(root.join("etc/hosts"), fs::Permissions::from_mode(0o666)).unwrap();
+        fim::scan_with(&cfg, Some(jsonl.to_string_lossy().to_string()), fim::ScanMode::Full).unwrap();
+        let content = fs::read_to_string(&jsonl).unwrap();
+        let line = content.lines().find(|l| l.contains("etc/hosts")).unwrap();
+        assert!(line.contains("\"kind\":\"perm_change\""));
+        assert!(line.contains("\"severity\":\"high\"") && line.contains("\"alert\":false"));
+    }
+}
+
+#[test]
+fn append_only_logs_report_truncate_and_rewrite() {
+    let dir = tempdir().unwrap();
+    let logs = dir.path().join("logs");
+    fs::create_dir(&logs).unwrap();
+    let log = logs.join("auth.log");
+    fs::write(&log, "session opened for root\n").unwrap();
+
+    let cfg_path = dir.path().join("config.toml");
+    fs::write(&cfg_path, format!(r#"
+baseline_db = "{db}"
+metrics_bind = "127.0.0.1:0"
+watch_paths = ["{root}"]
+
+[[rule]]
+glob = "**/*.log"
+check = ["perm", "owner"]
+log_mode = "append_only"
+prefix_bytes = 8
+"#, db = dir.path().join("base.db").display(), root = logs.display())).unwrap();
+    let cfg = Config::load(cfg_path.to_str().unwrap()).unwrap();
+    fim::build_baseline(&cfg).unwrap();
+
+    let mut content = fs::read_to_string(&log).unwrap();
+    content.push_str("session closed for root\n");
+    fs::write(&log, &content).unwrap();
+    let sum = fim::scan_with(&cfg, None, fim::ScanMode::Full).unwrap();
+    assert_eq!((sum.changed, sum.log_truncated, sum.log_rewritten), (0, 0, 0));
+
+    fs::write(&log, content.replacen("session", "SESSION", 1)).unwrap();
+    let sum = fim::scan_with(&cfg, None, fim::ScanMode::Full).unwrap();
+    assert_eq!((sum.log_truncated, sum.log_rewritten), (0, 1));
+
+    // past prefix_bytes, but at the end of what the baseline saw
+    fs::write(&log, content.replacen("for root\n", "for r00t\n", 1)).unwrap();
+    let sum = fim::scan_with(&cfg, None, fim::ScanMode::Full).unwrap();
+    assert_eq!((sum.log_truncated, sum.log_rewritten), (0, 1));
+
+    fs::write(&log, "").unwrap();
+    let jsonl = dir.path().join("diff.jsonl");
+    let sum = fim::scan_with(&cfg, Some(jsonl.to_string_lossy().to_string()), fim::ScanMode::Full).unwrap();
+    assert_eq!((sum.log_truncated, sum.log_rewritten), (1, 0));
+    let out = fs::read_to_string(&jsonl).unwrap();
+    assert!(out.contains("\"kind\":\"log_truncated\"") && out.contains("\"old_size\":24"));
+}
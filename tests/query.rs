@@ -0,0 +1,109 @@
+
+use std::path::PathBuf;
+use tempfile::tempdir;
+use sentra_fim::{
+    auditlog::AuditLog,
+    event::{AuditEvent, EventKind},
+    query::{self, Query},
+    rotate::{RotatingLog, RotationConfig},
+    sink::EventSink,
+};
+
+#[test]
+fn query_filters_across_rotated_files() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+    let cfg = RotationConfig { max_bytes: Some(400), keep: 10, compress: Some("gzip".to_string()), ..Default::default() };
+    let mut log = RotatingLog::new(AuditLog::open_append(&path).unwrap(), cfg).unwrap();
+
+    let base = query::parse_time("2026-01-01").unwrap();
+    for i in 0..12i64 {
+        let (kind, file) = if i % 3 == 0 { (EventKind::Delete, "/etc/app") } else { (EventKind::Modify, "/var/www") };
+        let mut e = AuditEvent::new(kind, format!("{file}/f{i}"));
+        e.ts = base + i * 3_600_000;
+        e.new_hash = Some(format!("{i:02}abcdef"));
+        log.emit(&e).unwrap();
+    }
+    drop(log);
+    assert!(std::path::Path::new(&format!("{path}.2.gz")).exists());
+    let logs = [PathBuf::from(&path)];
+
+    let all = query::run(&logs, &Query::default()).unwrap();
+    assert_eq!(all.len(), 12);
+    assert!(all.windows(2).all(|w| w[0].ts < w[1].ts), "oldest rotation first");
+
+    let deletes = query::run(&logs, &Query { kinds: vec![EventKind::Delete], ..Default::default() }).unwrap();
+    assert_eq!(deletes.len(), 4);
+
+    let window = Query {
+        since: Some(query::parse_time("2026-01-01T02:00:00Z").unwrap()),
+        until: Some(query::parse_time("2026-01-01T06:00:00Z").unwrap()),
+        path: Some("/var/www/**".to_string()),
+        ..Default::default()
+    };
+    let hits: Vec<String> = query::run(&logs, &window).unwrap().into_iter().map(|e| e.path).collect();
+    assert_eq!(hits, ["/var/www/f2", "/var/www/f4", "/var/www/f5"]);
+
+    let by_hash = query::run(&logs, &Query { hash: Some("07abc".to_string()), ..Default::default() }).unwrap();
+    assert_eq!(by_hash.len(), 1);
+    assert_eq!(by_hash[0].path, "/var/www/f7");
+
+    let counts = query::counts_by_dir(&all);
+    assert_eq!(counts["/etc/app"], 4);
+    assert_eq!(counts["/var/www"], 8);
+
+    assert_eq!(query::parse_time("1767225600").unwrap(), base);
+    assert!(query::parse_time("24h").unwrap() < query::parse_time("1h").unwrap());
+}
+
+#[test]
+fn query_merges_several_logs_by_time() {
+    let dir = tempdir().unwrap();
+    let base = query::parse_time("2026-01-01").unwrap();
+    let mut logs = Vec::new();
+    for (host, offset) in [("web1", 0i64), ("web2", 1)] {
+        let path = dir.path().join(format!("{host}.jsonl"));
+        let mut log = AuditLog::open_append(&path.to_string_lossy()).unwrap();
+        for i in 0..3i64 {
+            let mut e = AuditEvent::new(EventKind::Modify, format!("/{host}/f{i}"));
+            e.ts = base + (i * 2 + offset) * 1000;
+            log.emit(&e).unwrap();
+        }
+        logs.push(path);
+    }
+
+    let paths: Vec<String> = query::run(&logs, &Query::default()).unwrap().into_iter().map(|e| e.path).collect();
+    assert_eq!(paths, ["/web1/f0", "/web2/f0", "/web1/f1", "/web2/f1", "/web1/f2", "/web2/f2"]);
+
+    logs.push(dir.path().join("missing.jsonl"));
+    assert!(query::run(&logs, &Query::default()).is_err());
+}
+
+#[test]
+fn recent_reads_only_the_newest_rotations() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+    let cfg = RotationConfig { max_bytes: Some(400), keep: 10, ..Default::default() };
+    let mut log = RotatingLog::new(AuditLog::open_append(&path).unwrap(), cfg).unwrap();
+    let base = query::parse_time("2026-01-01").unwrap();
+    for i in 0..12i64 {
+        let mut e = AuditEvent::new(EventKind::Modify, format!("/var/www/f{i}"));
+        e.ts = base + i * 1000;
+        log.emit(&e).unwrap();
+    }
+    drop(log);
+    let oldest = query::run(&[PathBuf::from(&path)], &Query::default()).unwrap();
+    assert_eq!(oldest.len(), 12);
+
+    // an unreadable oldest rotation shows it is never opened
+    let files = sentra_fim::rotate::rotated_files(&path);
+    assert!(files.len() > 2);
+    std::fs::write(files.last().unwrap(), "not json\n").unwrap();
+
+    let paths: Vec<String> = query::recent(&path, base, 3).unwrap().into_iter().map(|e| e.path).collect();
+    assert_eq!(paths, ["/var/www/f9", "/var/www/f10", "/var/www/f11"]);
+    let since = base + 10_000;
+    assert_eq!(query::recent(&path, since, 100).unwrap().len(), 2);
+    assert!(query::recent(&path, base, 100).is_err());
+    assert!(query::recent(&dir.path().join("none.jsonl").to_string_lossy(), base, 10).unwrap().is_empty());
+}
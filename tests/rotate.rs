@@ -0,0 +1,68 @@
+
+use std::{fs, path::Path};
+use tempfile::tempdir;
+use sentra_fim::{
+    auditlog::{self, AuditLog},
+    event::{AuditEvent, EventKind, EventReader},
+    rotate::{self, RotatingLog, RotationConfig},
+    sink::EventSink,
+};
+
+fn event(i: usize) -> AuditEvent {
+    AuditEvent::new(EventKind::Modify, format!("/srv/file-{i}"))
+}
+
+#[test]
+fn size_rotation_keeps_and_compresses() {
+    for (alg, ext) in [("gzip", ".gz"), ("zstd", ".zst")] {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+        let cfg = RotationConfig { max_bytes: Some(600), keep: 2, compress: Some(alg.to_string()), ..Default::default() };
+        let mut log = RotatingLog::new(AuditLog::open_append(&path).unwrap(), cfg).unwrap();
+        for i in 0..20 {
+            log.emit(&event(i)).unwrap();
+        }
+
+        let files = rotate::rotated_files(&path);
+        assert_eq!(files, [path.clone(), format!("{path}.1{ext}"), format!("{path}.2{ext}")]);
+        assert!(!Path::new(&format!("{path}.3{ext}")).exists());
+
+        // newest rotated file ends right where the live one starts
+        let live: Vec<_> = EventReader::new(rotate::open_any(&files[0]).unwrap()).map(Result::unwrap).collect();
+        let prev: Vec<_> = EventReader::new(rotate::open_any(&files[1]).unwrap()).map(Result::unwrap).collect();
+        assert_eq!(live.last().unwrap().path, "/srv/file-19");
+        let last_rotated: usize = prev.last().unwrap().path.trim_start_matches("/srv/file-").parse().unwrap();
+        let first_live: usize = live[0].path.trim_start_matches("/srv/file-").parse().unwrap();
+        assert_eq!(last_rotated + 1, first_live);
+        assert!(auditlog::verify_log(&path).unwrap().broken.is_none());
+    }
+}
+
+#[test]
+fn copytruncate_is_recorded_and_reopen_restarts_the_chain() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("events.jsonl").to_string_lossy().to_string();
+    let mut log = AuditLog::open_append(&path).unwrap();
+    log.emit(&event(0)).unwrap();
+    log.emit(&event(1)).unwrap();
+
+    // copytruncate: same inode, emptied under us
+    fs::copy(&path, format!("{path}.1")).unwrap();
+    fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(0).unwrap();
+    let lost = log.head().to_string();
+    log.emit(&event(2)).unwrap();
+    // the cut is recorded, not papered over with a fresh chain
+    let report = auditlog::verify_log(&path).unwrap();
+    assert_eq!(report.records, 2);
+    assert!(report.broken.is_none());
+    assert_eq!(report.truncated.len(), 1);
+    assert_eq!((report.truncated[0].line, report.truncated[0].lost_head.as_str()), (1, lost.as_str()));
+
+    // move-and-signal: the file is renamed, SIGHUP makes us reopen the path
+    fs::rename(&path, format!("{path}.2")).unwrap();
+    EventSink::reopen(&mut log).unwrap();
+    log.emit(&event(3)).unwrap();
+    assert_eq!(EventReader::open(&path).unwrap().count(), 1);
+    assert!(auditlog::verify_log(&path).unwrap().broken.is_none());
+    assert_eq!(EventReader::open(&format!("{path}.2")).unwrap().count(), 2, "marker and event");
+}
@@ -0,0 +1,49 @@
+
+use std::fs;
+use tempfile::tempdir;
+use sentra_fim::{
+    auditlog::{self, AuditLog},
+    config::Config,
+    fim::{self, ScanMode},
+    sink::{FanOut, MemorySink},
+};
+
+#[test]
+fn scan_into_fans_out_to_memory_and_jsonl() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("b.txt"), "b").unwrap();
+
+    let cfg = Config {
+        baseline_db: dir.path().join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![root.to_string_lossy().to_string()],
+        debounce_ms: 10,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    };
+    fim::build_baseline(&cfg).unwrap();
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::remove_file(root.join("b.txt")).unwrap();
+    fs::write(root.join("c.txt"), "c").unwrap();
+
+    let mem = MemorySink::new();
+    let jsonl = dir.path().join("events.jsonl");
+    let mut out = FanOut::new()
+        .with(mem.clone())
+        .with(AuditLog::create(jsonl.to_str().unwrap()).unwrap());
+    let sum = fim::scan_into(&cfg, ScanMode::Full, &mut out).unwrap();
+    drop(out);
+
+    let mut kinds: Vec<&str> = mem.events().iter().map(|e| e.kind.as_str()).collect();
+    kinds.sort();
+    assert_eq!(kinds, ["added", "changed", "missing"]);
+    assert_eq!((sum.added, sum.changed, sum.missing), (1, 1, 1));
+    assert!(mem.events().iter().all(|e| e.prev.is_empty()));
+
+    // the file sink chains its own copy of the same events
+    let report = auditlog::verify_log(jsonl.to_str().unwrap()).unwrap();
+    assert_eq!(report.records, 3);
+    assert!(report.broken.is_none());
+}
@@ -0,0 +1,85 @@
+
+use std::{fs, net::UdpSocket, time::Duration};
+use tempfile::tempdir;
+use sentra_fim::{config::Config, fim};
+
+#[test]
+fn scan_sends_rfc5424_over_udp() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("b]\"q.txt"), "b").unwrap();
+
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let cfg_path = dir.path().join("config.toml");
+    fs::write(&cfg_path, format!(r#"
+baseline_db = "{db}"
+metrics_bind = "127.0.0.1:0"
+watch_paths = ["{root}"]
+
+[syslog]
+address = "{addr}"
+facility = "local3"
+
+[syslog.severity]
+missing = "crit"
+"#, db = dir.path().join("base.db").display(), root = root.display(), addr = sock.local_addr().unwrap())).unwrap();
+    let cfg = Config::load(cfg_path.to_str().unwrap()).unwrap();
+
+    fim::build_baseline(&cfg).unwrap();
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::remove_file(root.join("b]\"q.txt")).unwrap();
+    fim::scan_diff(&cfg, None).unwrap();
+
+    let mut msgs = Vec::new();
+    let mut buf = [0u8; 4096];
+    for _ in 0..2 {
+        let n = sock.recv(&mut buf).unwrap();
+        msgs.push(String::from_utf8_lossy(&buf[..n]).to_string());
+    }
+    msgs.sort();
+
+    // local3 = 19: changed -> notice (19*8+5), missing -> crit override (19*8+2)
+    let missing = &msgs[0];
+    let changed = &msgs[1];
+    assert!(changed.starts_with("<157>1 "), "{changed}");
+    assert!(missing.starts_with("<154>1 "), "{missing}");
+
+    let fields: Vec<&str> = changed.splitn(7, ' ').collect();
+    assert!(fields[1].ends_with('Z') && fields[1].contains('T'), "{changed}");
+    assert_eq!(fields[3], "sentra_fim");
+    assert_eq!(fields[5], "changed");
+    assert!(fields[6].starts_with("[fim@32473 "));
+    assert!(changed.contains(" severity=\"medium\""));
+    assert!(missing.contains("b\\]\\\"q.txt\""), "{missing}");
+}
+
+#[test]
+fn tcp_backs_off_after_a_failed_connect() {
+    use sentra_fim::{event::{AuditEvent, EventKind}, sink::EventSink, syslog::{Syslog, SyslogConfig}};
+    use std::{io::Read, net::TcpListener, thread};
+
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let cfg: SyslogConfig = toml::from_str(&format!("transport = \"tcp\"\naddress = \"{addr}\"")).unwrap();
+    let mut log = Syslog::open(cfg).unwrap();
+    let evt = AuditEvent::new(EventKind::Delete, "/etc/passwd".to_string());
+
+    // refused: delivery errors never reach the caller
+    log.emit(&evt).unwrap();
+    let listener = TcpListener::bind(addr).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    log.emit(&evt).unwrap();
+    assert!(listener.accept().is_err(), "no reconnect inside the backoff");
+
+    thread::sleep(Duration::from_millis(1100));
+    log.emit(&evt).unwrap();
+    let (mut conn, _) = listener.accept().unwrap();
+    conn.set_nonblocking(false).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let mut buf = [0u8; 4096];
+    let n = conn.read(&mut buf).unwrap();
+    assert!(String::from_utf8_lossy(&buf[..n]).contains("/etc/passwd"));
+}
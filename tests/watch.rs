@@ -0,0 +1,313 @@
+
+use std::{fs, thread, time::{Duration, Instant}};
+use tempfile::tempdir;
+use rusqlite::Connection;
+use serde_json::Value;
+use sentra_fim::{
+    config::Config, fim, health, history, http, metrics::Metrics,
+    policy::{Action, Rule}, sink::MemorySink,
+};
+
+fn config(dir: &std::path::Path, root: &std::path::Path) -> Config {
+    Config {
+        baseline_db: dir.join("base.db").to_string_lossy().to_string(),
+        watch_paths: vec![root.to_string_lossy().to_string()],
+        debounce_ms: 0,
+        events_table: true,
+        reconcile: "off".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Polls until `f` holds or a few seconds pass.
+fn eventually(mut f: impl FnMut() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if f() { return true; }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+/// The loop blocks its worker thread: never wait for it, even when an assert fails.
+struct Rt(Option<tokio::runtime::Runtime>);
+
+impl Drop for Rt {
+    fn drop(&mut self) { self.0.take().unwrap().shutdown_background(); }
+}
+
+/// Runs `watch_with` in the background until the returned runtime is dropped.
+fn start_watch(cfg: &Config, metrics: Metrics) -> (Rt, MemorySink) {
+    let rt = Rt(Some(tokio::runtime::Runtime::new().unwrap()));
+    let mem = MemorySink::new();
+    let mut sink = mem.clone();
+    let watch_cfg = cfg.clone();
+    rt.0.as_ref().unwrap().spawn(async move {
+        fim::watch_with(watch_cfg, metrics, &mut sink).await
+    });
+    thread::sleep(Duration::from_millis(300));
+    (rt, mem)
+}
+
+#[test]
+fn watch_records_events_in_sqlite() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    let cfg = config(dir.path(), &root);
+    fim::build_baseline(&cfg).unwrap();
+
+    let (rt, mem) = start_watch(&cfg, Metrics::try_new().unwrap());
+
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::write(root.join("b.txt"), "new").unwrap();
+    assert!(eventually(|| mem.events().iter().any(|e| e.path.ends_with("b.txt"))));
+
+    let db = Connection::open(&cfg.baseline_db).unwrap();
+    let stored: Vec<(String, String)> = db
+        .prepare("SELECT kind, path FROM events ORDER BY id").unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?))).unwrap()
+        .map(Result::unwrap)
+        .collect();
+    // everything the sinks saw was committed first
+    let seen: Vec<(String, String)> = mem.events().iter().map(|e| (e.kind.as_str().to_string(), e.path.clone())).collect();
+    assert!(seen.iter().all(|s| stored.contains(s)), "{seen:?} vs {stored:?}");
+    assert!(stored.iter().any(|(k, p)| k == "modify" && p.ends_with("a.txt")));
+    assert!(stored.iter().any(|(k, p)| k == "create" && p.ends_with("b.txt")));
+    let gens = history::list_generations(&cfg).unwrap();
+    assert!(gens[0].active && gens[0].dirty, "files moved on from the snapshot");
+
+    let plan: String = db
+        .query_row("EXPLAIN QUERY PLAN SELECT * FROM events WHERE path = 'x' AND ts > 0", [], |r| r.get(3))
+        .unwrap();
+    assert!(plan.contains("events_path"), "{plan}");
+
+    // rolling back to the same generation keeps what watch recorded
+    drop(rt);
+    history::rollback(&cfg, gens[0].id).unwrap();
+    let gens = history::list_generations(&cfg).unwrap();
+    assert_eq!(gens.len(), 2);
+    assert!(gens[0].active && !gens[0].dirty);
+    let kept = history::diff_generations(&cfg, gens[0].id, gens[1].id).unwrap();
+    assert!(kept.added[0].ends_with("b.txt") && kept.changed[0].ends_with("a.txt"), "{kept:?}");
+}
+
+#[test]
+fn watch_metrics_by_root_kind_and_hashing() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    let cfg = Config { events_table: false, ..config(dir.path(), &root) };
+    fim::build_baseline(&cfg).unwrap();
+
+    let metrics = Metrics::try_new().unwrap();
+    let (_rt, mem) = start_watch(&cfg, metrics.clone());
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::write(root.join("b.txt"), "0123456789").unwrap();
+    assert!(eventually(|| mem.events().iter().any(|e| e.path.ends_with("b.txt"))));
+
+    let root = dunce::canonicalize(&root).unwrap().to_string_lossy().to_string();
+    let count = |kind: &str| metrics.events.with_label_values(&[&root, kind]).get();
+    assert!(count("modify") >= 1);
+    assert_eq!(count("create"), 1);
+    assert!(metrics.hash_seconds.get_sample_count() >= 2);
+    assert!(metrics.hashed_bytes.get() >= 17);
+    assert!(eventually(|| metrics.queue_depth.get() == 0));
+    assert_eq!(metrics.hash_errors.get(), 0);
+    assert_eq!(metrics.last_scan.get(), 0);
+
+    fim::scan_metered(&cfg, fim::ScanMode::Full, &mut Vec::new(), &metrics, None).unwrap();
+    assert!(metrics.last_scan.get() > 1_700_000_000);
+}
+
+#[test]
+fn health_and_readiness_follow_the_loop_and_db() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    let cfg = config(dir.path(), &root);
+    fim::build_baseline(&cfg).unwrap();
+
+    let metrics = Metrics::try_new().unwrap();
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let server = tokio::runtime::Runtime::new().unwrap();
+    server.block_on(http::serve(&addr.to_string(), health::router(cfg.clone(), metrics.clone()), None)).unwrap();
+    let probe = |path: &str| -> (u16, Value) {
+        let resp = match ureq::get(&format!("http://{addr}{path}")).call() {
+            Ok(r) | Err(ureq::Error::Status(_, r)) => r,
+            Err(e) => panic!("{e}"),
+        };
+        (resp.status(), serde_json::from_str(&resp.into_string().unwrap()).unwrap())
+    };
+
+    let (code, body) = probe("/healthz");
+    assert_eq!(code, 503);
+    assert_eq!(body["checks"]["heartbeat"]["ok"], false);
+    assert_eq!(body["checks"]["db"]["ok"], true);
+    assert_eq!(probe("/readyz").0, 503);
+
+    let (_rt, _) = start_watch(&cfg, metrics.clone());
+    assert!(eventually(|| probe("/healthz").0 == 200));
+    assert_eq!(probe("/readyz").1["ready"], true);
+
+    // another writer holds the lock: the DB check fails, then recovers
+    let db = rusqlite::Connection::open(&cfg.baseline_db).unwrap();
+    db.execute_batch("BEGIN EXCLUSIVE").unwrap();
+    let (code, body) = probe("/healthz");
+    assert_eq!(code, 503);
+    assert_eq!(body["checks"]["db"]["ok"], false, "{body}");
+    db.execute_batch("ROLLBACK").unwrap();
+    assert_eq!(probe("/healthz").0, 200);
+}
+
+#[test]
+fn startup_reconcile_reports_downtime_drift_then_live_events() {
+    for mode in ["foreground", "background"] {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        let cfg = Config { reconcile: mode.to_string(), ..config(dir.path(), &root) };
+        fim::build_baseline(&cfg).unwrap();
+
+        // while the daemon is down
+        fs::write(root.join("a.txt"), "changed").unwrap();
+        fs::remove_file(root.join("b.txt")).unwrap();
+        fs::write(root.join("c.txt"), "new").unwrap();
+
+        let metrics = Metrics::try_new().unwrap();
+        let (_rt, mem) = start_watch(&cfg, metrics.clone());
+        assert!(eventually(|| metrics.ready.get() == 1), "{mode}");
+        let drift: Vec<(String, String)> = mem.take().into_iter()
+            .map(|e| {
+                assert_eq!(e.source.as_deref(), Some("reconcile"), "{e:?}");
+                (e.kind.as_str().to_string(), e.path.rsplit('/').next().unwrap().to_string())
+            })
+            .collect();
+        for want in [("modify", "a.txt"), ("delete", "b.txt"), ("create", "c.txt")] {
+            assert!(drift.contains(&(want.0.to_string(), want.1.to_string())), "{mode}: {drift:?}");
+        }
+        assert_eq!(drift.len(), 3, "{mode}: {drift:?}");
+
+        fs::write(root.join("d.txt"), "live").unwrap();
+        assert!(eventually(|| mem.events().iter().any(|e| e.path.ends_with("d.txt"))));
+        assert!(mem.events().iter().all(|e| e.source.is_none()));
+    }
+}
+
+#[test]
+fn periodic_rescan_reports_only_what_the_watcher_missed() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("b.txt"), "b").unwrap();
+    let cfg = Config { rescan_interval: 1, rescan_bytes_per_sec: 1 << 20, ..config(dir.path(), &root) };
+    fim::build_baseline(&cfg).unwrap();
+
+    let metrics = Metrics::try_new().unwrap();
+    let (_rt, mem) = start_watch(&cfg, metrics.clone());
+    // a live change is the watcher's; the rescan must not repeat it
+    fs::write(root.join("c.txt"), "live").unwrap();
+    assert!(eventually(|| mem.events().iter().any(|e| e.path.ends_with("c.txt"))));
+
+    // the baseline forgets changes no inotify event will bring back
+    let db = Connection::open(&cfg.baseline_db).unwrap();
+    db.execute("UPDATE files SET hash = 'stale' WHERE path LIKE '%/a.txt'", []).unwrap();
+    db.execute("DELETE FROM files WHERE path LIKE '%/b.txt'", []).unwrap();
+
+    let missed = || mem.events().into_iter().filter(|e| e.kind.as_str() == "missed_event").collect::<Vec<_>>();
+    assert!(eventually(|| missed().len() >= 2), "{:?}", mem.events());
+    let scanned = metrics.last_scan.get();
+    assert!(eventually(|| metrics.last_scan.get() > scanned), "another rescan");
+    thread::sleep(Duration::from_millis(500));
+
+    let mut found: Vec<(String, String)> = missed().into_iter()
+        .map(|e| {
+            assert_eq!(e.source.as_deref(), Some("rescan"), "{e:?}");
+            (e.missed.unwrap().as_str().to_string(), e.path.rsplit('/').next().unwrap().to_string())
+        })
+        .collect();
+    found.sort();
+    assert_eq!(found, [("create".to_string(), "b.txt".to_string()), ("modify".to_string(), "a.txt".to_string())]);
+    assert!(mem.events().iter().filter(|e| e.path.ends_with("c.txt")).all(|e| e.source.is_none()));
+    let stored: i64 = db.query_row("SELECT COUNT(*) FROM events WHERE kind = 'missed_event'", [], |r| r.get(0)).unwrap();
+    assert_eq!(stored, 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn metadata_events_report_exact_kinds() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    let f = root.join("a.txt");
+    fs::write(&f, "a").unwrap();
+    // the default window, so a chmod could swallow the write right after it
+    let cfg = Config { debounce_ms: 250, ..config(dir.path(), &root) };
+    fim::build_baseline(&cfg).unwrap();
+    let (_rt, mem) = start_watch(&cfg, Metrics::try_new().unwrap());
+
+    let kinds_after = |want: &[&str]| {
+        assert!(eventually(|| mem.events().len() >= want.len()), "{want:?}: {:?}", mem.events());
+        thread::sleep(Duration::from_millis(400));
+        let mut got: Vec<String> = mem.take().iter().map(|e| e.kind.as_str().to_string()).collect();
+        got.sort();
+        got.dedup();
+        assert_eq!(got, want);
+    };
+
+    fs::set_permissions(&f, fs::Permissions::from_mode(0o600)).unwrap();
+    kinds_after(&["perm_change"]);
+
+    if std::os::unix::fs::chown(&f, Some(4242), Some(4242)).is_ok() {
+        kinds_after(&["owner_change"]);
+    }
+
+    fs::File::options().write(true).open(&f).unwrap()
+        .set_modified(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000)).unwrap();
+    kinds_after(&["times_change"]);
+
+    // chmod, then same-size new content inside the debounce window
+    thread::sleep(Duration::from_millis(300));
+    fs::set_permissions(&f, fs::Permissions::from_mode(0o640)).unwrap();
+    fs::write(&f, "b").unwrap();
+    kinds_after(&["modify", "perm_change"]);
+}
+
+#[test]
+fn renames_across_an_ignore_rule_are_deletes_and_creates() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(root.join("scratch")).unwrap();
+    fs::write(root.join("a.conf"), "a").unwrap();
+    fs::write(root.join("scratch/b.conf"), "b").unwrap();
+    let cfg = Config {
+        rules: vec![Rule { glob: "**/scratch/**".to_string(), action: Action::Ignore, ..Default::default() }],
+        ..config(dir.path(), &root)
+    };
+    fim::build_baseline(&cfg).unwrap();
+
+    let (_rt, mem) = start_watch(&cfg, Metrics::try_new().unwrap());
+    fs::rename(root.join("a.conf"), root.join("scratch/a.conf")).unwrap();
+    fs::rename(root.join("scratch/b.conf"), root.join("b.conf")).unwrap();
+    assert!(eventually(|| mem.events().len() >= 2));
+    thread::sleep(Duration::from_millis(200));
+
+    let mut seen: Vec<(String, String)> = mem.events().iter()
+        .map(|e| (e.kind.as_str().to_string(), e.path.rsplit('/').next().unwrap().to_string()))
+        .collect();
+    seen.sort();
+    assert_eq!(seen, [("create".to_string(), "b.conf".to_string()), ("delete".to_string(), "a.conf".to_string())]);
+    assert!(mem.events().iter().all(|e| !e.path.contains("/scratch/")));
+
+    let db = Connection::open(&cfg.baseline_db).unwrap();
+    let paths: Vec<String> = db.prepare("SELECT path FROM files ORDER BY path").unwrap()
+        .query_map([], |r| r.get(0)).unwrap().map(Result::unwrap).collect();
+    assert_eq!(paths.len(), 1);
+    assert!(paths[0].ends_with("root/b.conf"), "{paths:?}");
+}
@@ -0,0 +1,109 @@
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+use tempfile::tempdir;
+use sentra_fim::{config::Config, fim};
+
+/// Minimal HTTP server: answers `statuses` in turn (then 200) and records request bodies.
+fn stub(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let seen = bodies.clone();
+    thread::spawn(move || {
+        let mut statuses = statuses.into_iter();
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() { break; }
+                if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    len = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            let status = statuses.next().unwrap_or(200);
+            if status == 200 {
+                seen.lock().unwrap().push(String::from_utf8(body).unwrap());
+            }
+            write!(stream, "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        }
+    });
+    (url, bodies)
+}
+
+#[test]
+fn webhook_batches_and_keeps_undelivered_queue() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("b.txt"), "b").unwrap();
+
+    let write_cfg = |url: &str| {
+        let cfg_path = dir.path().join("config.toml");
+        fs::write(&cfg_path, format!(r#"
+baseline_db = "{db}"
+metrics_bind = "127.0.0.1:0"
+watch_paths = ["{root}"]
+
+[webhook]
+url = "{url}"
+template = '{{"text": "{{{{kind}}}} {{{{path}}}}", "severity": "{{{{severity}}}}"}}'
+envelope = '{{"source": "fim", "events": {{{{events}}}}}}'
+flush_ms = 10
+backoff_ms = 10
+timeout_ms = 2000
+"#, db = dir.path().join("base.db").display(), root = root.display())).unwrap();
+        Config::load(cfg_path.to_str().unwrap()).unwrap()
+    };
+
+    let (down, _) = stub(vec![503; 100]);
+    let cfg = write_cfg(&down);
+    fim::build_baseline(&cfg).unwrap();
+    fs::write(root.join("a.txt"), "changed").unwrap();
+    fs::remove_file(root.join("b.txt")).unwrap();
+    fim::scan_diff(&cfg, None).unwrap();
+
+    // receiver was down: both events wait in the on-disk queue
+    let queue = fs::read_to_string(format!("{}.webhook-queue", cfg.baseline_db)).unwrap();
+    assert_eq!(queue.lines().count(), 2, "{queue}");
+
+    // next run picks up the queue, backs off after a 500 and delivers everything
+    let (up, bodies) = stub(vec![500]);
+    let cfg = write_cfg(&up);
+    fs::write(root.join("c.txt"), "c").unwrap();
+    fim::scan_diff(&cfg, None).unwrap();
+
+    let bodies = bodies.lock().unwrap();
+    let all: Vec<serde_json::Value> = bodies.iter().map(|b| serde_json::from_str(b).unwrap()).collect();
+    assert!(all.iter().all(|b| b["source"] == "fim"));
+    let mut events: Vec<(String, String, String)> = all.iter()
+        .flat_map(|b| b["events"].as_array().unwrap())
+        .map(|e| {
+            let (kind, path) = e["text"].as_str().unwrap().split_once(' ').unwrap();
+            (path.to_string(), kind.to_string(), e["severity"].as_str().unwrap().to_string())
+        })
+        .collect();
+    events.sort();
+    let file = |name: &str| root.join(name).to_string_lossy().to_string();
+    let kinds: Vec<(String, &str)> = events.iter().map(|(p, k, _)| (p.clone(), k.as_str())).collect();
+    // the queued pair from the first run plus this scan's own report
+    assert_eq!(kinds, [
+        (file("a.txt"), "changed"), (file("a.txt"), "changed"),
+        (file("b.txt"), "missing"), (file("b.txt"), "missing"),
+        (file("c.txt"), "added"),
+    ]);
+    assert_eq!(events[0].2, "medium");
+    let queue = fs::read_to_string(format!("{}.webhook-queue", cfg.baseline_db)).unwrap();
+    assert!(queue.is_empty(), "{queue}");
+}